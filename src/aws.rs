@@ -1,7 +1,129 @@
+pub(crate) mod autoscaling;
 pub(crate) mod cf;
 pub(crate) mod ec2;
+pub(crate) mod eks;
+pub(crate) mod elb;
+pub(crate) mod lambda;
+pub(crate) mod rds;
 
 pub(crate) use ec2::get_all_regions;
 
-pub(crate) use cf::CfResources;
+pub(crate) use cf::{CfEvents, CfExports, CfResources};
 pub(crate) use ec2::Ec2Resources;
+pub(crate) use eks::EksResources;
+pub(crate) use elb::ElbResources;
+pub(crate) use lambda::LambdaResources;
+pub(crate) use rds::RdsResources;
+
+use std::future::Future;
+
+use aws_sdk_rds::error::ProvideErrorMetadata;
+use duplicate::duplicate_item;
+
+/// Whether AWS refused a call for lack of permission, which only shows in the error code
+pub(crate) fn is_access_denied(error: &anyhow::Error) -> bool {
+    macro_rules! code {
+        ($($service:ident),+) => {
+            None$(.or_else(|| match error.downcast_ref::<$service::Error>() {
+                Some($service::Error::Unhandled(unhandled)) => unhandled.code(),
+                _ => None,
+            }))+
+        };
+    }
+    let code: Option<&str> = code!(
+        aws_sdk_autoscaling,
+        aws_sdk_cloudformation,
+        aws_sdk_ec2,
+        aws_sdk_eks,
+        aws_sdk_elasticloadbalancing,
+        aws_sdk_elasticloadbalancingv2,
+        aws_sdk_lambda,
+        aws_sdk_rds
+    );
+    matches!(
+        code,
+        Some("AccessDenied" | "AccessDeniedException" | "UnauthorizedOperation")
+    )
+}
+
+/// Requests a service sends at the same time within a region. AWS throttles every service per
+/// region, so this does not depend on how many regions are explored at once.
+const REQUESTS_PER_REGION: usize = 4;
+
+/// Bounds the requests of one service client, however deeply they fan out
+#[derive(Debug)]
+pub(crate) struct Requests(tokio::sync::Semaphore);
+
+impl Requests {
+    pub(crate) fn new() -> Self {
+        Self(tokio::sync::Semaphore::new(REQUESTS_PER_REGION))
+    }
+
+    pub(crate) async fn send<F: Future>(&self, request: F) -> F::Output {
+        let _permit = self.0.acquire().await.expect("Requests are never closed");
+        request.await
+    }
+
+    /// One request per resource, keeping the order of the resources
+    pub(crate) async fn send_each<T, U, E, F>(
+        &self,
+        items: impl IntoIterator<Item = T>,
+        call: impl FnMut(T) -> F,
+    ) -> Result<Vec<U>, E>
+    where
+        F: Future<Output = Result<U, E>>,
+    {
+        futures::future::try_join_all(
+            items
+                .into_iter()
+                .map(call)
+                .map(|request| self.send(request)),
+        )
+        .await
+    }
+}
+
+/// The tags of the different services are distinct types with the same accessors
+pub(crate) trait Tag {
+    fn key(&self) -> Option<&str>;
+
+    fn value(&self) -> Option<&str>;
+}
+
+#[duplicate_item(
+    tag;
+    [aws_sdk_elasticloadbalancing::types::Tag];
+    [aws_sdk_elasticloadbalancingv2::types::Tag];
+    [aws_sdk_rds::types::Tag];
+)]
+impl Tag for tag {
+    fn key(&self) -> Option<&str> {
+        self.key()
+    }
+
+    fn value(&self) -> Option<&str> {
+        self.value()
+    }
+}
+
+/// Whether the tags include every requested key and value
+pub(crate) fn has_tags(requested: &[(String, String)], tags: Option<&[impl Tag]>) -> bool {
+    let tags = tags.unwrap_or_default();
+    requested.iter().all(|(key, value)| {
+        tags.iter()
+            .any(|tag| tag.key() == Some(key.as_str()) && tag.value() == Some(value.as_str()))
+    })
+}
+
+pub(crate) fn add_detail(tree: &mut ptree::TreeBuilder, title: &str, value: Option<&str>) {
+    if let Some(value) = value {
+        tree.add_empty_child(format!("{title}: {value}"));
+    }
+}
+
+pub(crate) fn add_list(tree: &mut ptree::TreeBuilder, title: &str, values: &[impl AsRef<str>]) {
+    if !values.is_empty() {
+        let values = values.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+        tree.add_empty_child(format!("{title}: {}", values.join(", ")));
+    }
+}
@@ -0,0 +1,133 @@
+use aws_sdk_autoscaling as autoscaling;
+use tokio_stream::StreamExt;
+
+/// The tag Auto Scaling puts on every instance it launches
+pub(crate) const GROUP_NAME_TAG: &str = "aws:autoscaling:groupName";
+
+#[derive(Debug)]
+pub(crate) struct AutoScalingResources {
+    client: autoscaling::Client,
+    groups: Vec<autoscaling::types::AutoScalingGroup>,
+    launch_configurations: Vec<autoscaling::types::LaunchConfiguration>,
+}
+
+impl AutoScalingResources {
+    pub(crate) fn new(config: &aws_types::SdkConfig) -> Self {
+        let client = autoscaling::Client::new(config);
+
+        Self {
+            client,
+            groups: vec![],
+            launch_configurations: vec![],
+        }
+    }
+
+    pub(crate) async fn collect(
+        &mut self,
+        progress: &indicatif::ProgressBar,
+    ) -> Result<(), autoscaling::Error> {
+        progress.inc_length(2);
+        progress.set_message("Auto Scaling Groups");
+
+        let (groups, launch_configurations) =
+            tokio::try_join!(self.collect_groups(), self.collect_launch_configurations())?;
+        self.groups = groups;
+        self.launch_configurations = launch_configurations;
+        progress.inc(2);
+
+        Ok(())
+    }
+
+    async fn collect_groups(
+        &self,
+    ) -> Result<Vec<autoscaling::types::AutoScalingGroup>, autoscaling::Error> {
+        let groups = self
+            .client
+            .describe_auto_scaling_groups()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(groups)
+    }
+
+    async fn collect_launch_configurations(
+        &self,
+    ) -> Result<Vec<autoscaling::types::LaunchConfiguration>, autoscaling::Error> {
+        let launch_configurations = self
+            .client
+            .describe_launch_configurations()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(launch_configurations)
+    }
+
+    pub(crate) fn group(&self, name: &str) -> Option<&autoscaling::types::AutoScalingGroup> {
+        self.groups
+            .iter()
+            .find(|group| group.auto_scaling_group_name() == Some(name))
+    }
+
+    /// Groups launching instances in any of these subnets
+    pub(crate) fn groups_in(
+        &self,
+        subnet_ids: &[&str],
+    ) -> Vec<&autoscaling::types::AutoScalingGroup> {
+        self.groups
+            .iter()
+            .filter(|group| {
+                group_subnets(group)
+                    .iter()
+                    .any(|subnet_id| subnet_ids.contains(subnet_id))
+            })
+            .collect()
+    }
+
+    pub(crate) fn launch_configuration(
+        &self,
+        name: &str,
+    ) -> Option<&autoscaling::types::LaunchConfiguration> {
+        self.launch_configurations
+            .iter()
+            .find(|launch_configuration| {
+                launch_configuration.launch_configuration_name() == Some(name)
+            })
+    }
+}
+
+pub(crate) fn group_title(group: &autoscaling::types::AutoScalingGroup) -> String {
+    let name = group.auto_scaling_group_name().unwrap_or("no name");
+    let desired = group.desired_capacity().unwrap_or_default();
+    let min = group.min_size().unwrap_or_default();
+    let max = group.max_size().unwrap_or_default();
+    format!("Auto Scaling Group {name} (desired {desired}, min {min}, max {max})")
+}
+
+/// The launch template of a group, given either directly or through a mixed instances policy
+pub(crate) fn launch_template(
+    group: &autoscaling::types::AutoScalingGroup,
+) -> Option<&autoscaling::types::LaunchTemplateSpecification> {
+    group.launch_template().or_else(|| {
+        group
+            .mixed_instances_policy()?
+            .launch_template()?
+            .launch_template_specification()
+    })
+}
+
+/// The group only knows its subnets as a comma separated list
+pub(crate) fn group_subnets(group: &autoscaling::types::AutoScalingGroup) -> Vec<&str> {
+    group
+        .vpc_zone_identifier()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|subnet_id| !subnet_id.is_empty())
+        .collect()
+}
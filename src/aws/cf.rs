@@ -3,13 +3,34 @@ use std::collections::BTreeSet;
 use aws_sdk_cloudformation as cf;
 use tokio_stream::StreamExt;
 
+use crate::aws::Requests;
+use crate::mermaid;
+use crate::output::Render;
+
 pub(crate) use cf::types::StackStatus;
+pub(crate) use events::CfEvents;
+pub(crate) use exports::CfExports;
+
+mod events;
+mod exports;
+
+/// The most resources a single `DescribeStackResources` call returns for a stack
+const DESCRIBE_STACK_RESOURCES_LIMIT: usize = 100;
+
+const NESTED_STACK_TYPE: &str = "AWS::CloudFormation::Stack";
 
 #[derive(Debug)]
 pub(crate) struct CfResources {
     client: cf::Client,
+    requests: Requests,
+    /// Whether all stacks are explored rather than the requested ones
+    all_stacks: bool,
     stacks: Vec<cf::types::StackSummary>,
-    resources: Vec<(cf::types::StackSummary, Vec<cf::types::StackResource>)>,
+    descriptions: Vec<cf::types::Stack>,
+    resources: Vec<(
+        cf::types::StackSummary,
+        Vec<cf::types::StackResourceSummary>,
+    )>,
 }
 
 impl CfResources {
@@ -18,7 +39,10 @@ impl CfResources {
 
         Self {
             client,
+            requests: Requests::new(),
+            all_stacks: true,
             stacks: vec![],
+            descriptions: vec![],
             resources: vec![],
         }
     }
@@ -27,25 +51,58 @@ impl CfResources {
         &mut self,
         stacks: &[String],
         statuses: &[StackStatus],
+        root_only: bool,
     ) -> Result<(), cf::Error> {
-        let requested = stacks
+        self.all_stacks = stacks.is_empty();
+        self.stacks = list_stacks(&self.client, stacks, statuses)
+            .await?
+            .into_iter()
+            .filter(|stack| !root_only || stack.parent_id().is_none())
+            .collect();
+
+        Ok(())
+    }
+
+    /// The summaries leave out parameters, outputs and tags, so describe the stacks as well:
+    /// all of them in one go, or each requested stack and the stacks nested below it by id
+    pub(crate) async fn collect_stack_descriptions(
+        &mut self,
+        progress: &indicatif::ProgressBar,
+    ) -> Result<(), cf::Error> {
+        progress.set_message("Describing stacks");
+        let ids = self
+            .stacks
             .iter()
-            .map(|s| s.as_str())
-            .map(Some)
+            .filter_map(|stack| stack.stack_id())
             .collect::<BTreeSet<_>>();
-        let list_stacks = self.client.list_stacks();
-        self.stacks = statuses
-            .iter()
-            .fold(list_stacks, |list, status| {
-                list.stack_status_filter(status.clone())
-            })
-            .into_paginator()
-            .items()
-            .send()
-            .filter_map(|stack| stack.ok())
-            .filter(|stack| is_requested(stack, &requested))
-            .collect()
-            .await;
+        let descriptions = if self.all_stacks {
+            self.client
+                .describe_stacks()
+                .into_paginator()
+                .items()
+                .send()
+                .collect::<Result<Vec<_>, _>>()
+                .await?
+        } else {
+            self.requests
+                .send_each(&ids, |id| {
+                    self.client
+                        .describe_stacks()
+                        .stack_name(*id)
+                        .into_paginator()
+                        .items()
+                        .send()
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .await?
+                .into_iter()
+                .flatten()
+                .collect()
+        };
+        self.descriptions = descriptions
+            .into_iter()
+            .filter(|stack| stack.stack_id().map_or(false, |id| ids.contains(id)))
+            .collect();
 
         Ok(())
     }
@@ -71,45 +128,352 @@ impl CfResources {
         Ok(())
     }
 
-    pub(crate) fn trees(&self) -> impl Iterator<Item = ptree::item::StringItem> + '_ {
-        self.resources
-            .iter()
-            .map(|(stack, resources)| stack_tree(stack, resources))
-    }
-
     async fn collect_resources(
         &self,
         stack_name: &str,
-    ) -> Result<Vec<cf::types::StackResource>, cf::Error> {
+    ) -> Result<Vec<cf::types::StackResourceSummary>, cf::Error> {
         let resources = self
             .client
-            .describe_stack_resources()
+            .list_stack_resources()
             .stack_name(stack_name)
+            .into_paginator()
+            .items()
             .send()
-            .await?
-            .stack_resources
-            .unwrap_or_default();
-        // let resources = self
-        //     .client
-        //     .list_stack_resources()
-        //     .stack_name(stack_name)
-        //     .into_paginator()
-        //     .items()
-        //     .send()
-        //     .collect::<Result<_, _>>()
-        //     .await?;
+            .collect::<Result<_, _>>()
+            .await?;
 
         Ok(resources)
     }
+
+    fn description(&self, stack: &cf::types::StackSummary) -> Option<&cf::types::Stack> {
+        self.descriptions
+            .iter()
+            .find(|description| description.stack_id() == stack.stack_id())
+    }
+
+    fn nested_stack(
+        &self,
+        resource: &cf::types::StackResourceSummary,
+    ) -> Option<&(
+        cf::types::StackSummary,
+        Vec<cf::types::StackResourceSummary>,
+    )> {
+        if resource.resource_type() != Some(NESTED_STACK_TYPE) {
+            return None;
+        }
+        let stack_id = resource.physical_resource_id()?;
+        self.resources
+            .iter()
+            .find(|(stack, _)| stack.stack_id() == Some(stack_id))
+    }
+
+    /// Nested stacks are shown below their parent, unless the parent has not been collected
+    fn top_level_stacks(
+        &self,
+    ) -> impl Iterator<
+        Item = &(
+            cf::types::StackSummary,
+            Vec<cf::types::StackResourceSummary>,
+        ),
+    > {
+        self.resources.iter().filter(move |(stack, _)| {
+            stack.parent_id().map_or(true, |parent_id| {
+                !self
+                    .resources
+                    .iter()
+                    .any(|(parent, _)| parent.stack_id() == Some(parent_id))
+            })
+        })
+    }
+
+    fn stack_tree(
+        &self,
+        stack: &cf::types::StackSummary,
+        resources: &[cf::types::StackResourceSummary],
+    ) -> ptree::item::StringItem {
+        let title = self
+            .description(stack)
+            .map_or_else(|| stack.title(), |description| description.title());
+        let mut tree = ptree::TreeBuilder::new(title);
+        self.add_stack(&mut tree, stack, resources);
+        tree.build()
+    }
+
+    fn add_stack(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        stack: &cf::types::StackSummary,
+        resources: &[cf::types::StackResourceSummary],
+    ) {
+        if let Some(description) = self.description(stack) {
+            add_description(tree, description);
+        }
+        if resources.len() > DESCRIBE_STACK_RESOURCES_LIMIT {
+            tree.add_empty_child(format!(
+                "{} resources (more than the {DESCRIBE_STACK_RESOURCES_LIMIT} a single call returns)",
+                resources.len()
+            ));
+        }
+        resources.iter().for_each(|resource| {
+            tree.begin_child(resource.title());
+            let r#type = resource.resource_type().unwrap_or("no type");
+            let id = resource.physical_resource_id().unwrap_or("no id");
+            tree.add_empty_child(format!("{type:40}: {id}"));
+            if let Some((stack, resources)) = self.nested_stack(resource) {
+                self.add_stack(tree, stack, resources);
+            }
+            tree.end_child();
+        })
+    }
+
+    fn stack_mermaid(
+        &self,
+        stack: &cf::types::StackSummary,
+        resources: &[cf::types::StackResourceSummary],
+    ) -> Vec<String> {
+        // Stack names repeat across regions (StackSets deploy the same stack everywhere),
+        // the stack id is unique
+        let stack_id = stack
+            .stack_id()
+            .or_else(|| stack.stack_name())
+            .unwrap_or_default();
+        let statements = resources
+            .iter()
+            .flat_map(|resource| match self.nested_stack(resource) {
+                Some((stack, resources)) => self.stack_mermaid(stack, resources),
+                None => {
+                    let id = format!(
+                        "{stack_id}_{}",
+                        resource.logical_resource_id().unwrap_or_default()
+                    );
+                    let r#type = resource.resource_type().unwrap_or("no type");
+                    vec![mermaid::node(id, format!("{} {type}", resource.title()))]
+                }
+            })
+            .collect();
+        mermaid::subgraph(stack_id, stack.title(), statements)
+    }
+}
+
+impl Render for CfResources {
+    fn trees(&self) -> Vec<ptree::item::StringItem> {
+        self.top_level_stacks()
+            .map(|(stack, resources)| self.stack_tree(stack, resources))
+            .collect()
+    }
+
+    fn json(&self) -> serde_json::Value {
+        let stacks = self
+            .resources
+            .iter()
+            .map(|(stack, resources)| stack_json(stack, self.description(stack), resources))
+            .collect::<Vec<_>>();
+        serde_json::json!({ "stacks": stacks })
+    }
+
+    fn mermaid(&self) -> Option<Vec<String>> {
+        let stacks = self
+            .top_level_stacks()
+            .flat_map(|(stack, resources)| self.stack_mermaid(stack, resources))
+            .collect();
+        Some(stacks)
+    }
 }
 
-fn stack_tree(
+fn add_description(tree: &mut ptree::TreeBuilder, stack: &cf::types::Stack) {
+    if let Some(description) = stack.description() {
+        tree.add_empty_child(format!("Description: {description}"));
+    }
+    if let Some(created) = stack.creation_time().and_then(date_time) {
+        tree.add_empty_child(format!("Created: {created}"));
+    }
+    if let Some(updated) = stack.last_updated_time().and_then(date_time) {
+        tree.add_empty_child(format!("Last Updated: {updated}"));
+    }
+    let protection = if stack.enable_termination_protection().unwrap_or_default() {
+        "enabled"
+    } else {
+        "disabled"
+    };
+    tree.add_empty_child(format!("Termination Protection: {protection}"));
+    let capabilities = stack
+        .capabilities()
+        .unwrap_or_default()
+        .iter()
+        .map(|capability| capability.as_str())
+        .collect::<Vec<_>>();
+    if !capabilities.is_empty() {
+        tree.add_empty_child(format!("Capabilities: {}", capabilities.join(", ")));
+    }
+
+    let parameters = stack.parameters().unwrap_or_default();
+    if !parameters.is_empty() {
+        tree.begin_child(String::from("Parameters"));
+        for parameter in parameters {
+            let key = parameter.parameter_key().unwrap_or_default();
+            let value = parameter.parameter_value().unwrap_or_default();
+            match parameter.resolved_value() {
+                Some(resolved) => tree.add_empty_child(format!("{key}: {value} ({resolved})")),
+                None => tree.add_empty_child(format!("{key}: {value}")),
+            };
+        }
+        tree.end_child();
+    }
+
+    let outputs = stack.outputs().unwrap_or_default();
+    if !outputs.is_empty() {
+        tree.begin_child(String::from("Outputs"));
+        for output in outputs {
+            let key = output.output_key().unwrap_or_default();
+            let value = output.output_value().unwrap_or_default();
+            match output.export_name() {
+                Some(export) => tree.add_empty_child(format!("{key}: {value} (export {export})")),
+                None => tree.add_empty_child(format!("{key}: {value}")),
+            };
+        }
+        tree.end_child();
+    }
+
+    let tags = stack.tags().unwrap_or_default();
+    if !tags.is_empty() {
+        tree.begin_child(String::from("Tags"));
+        for tag in tags {
+            let key = tag.key().unwrap_or_default();
+            let value = tag.value().unwrap_or_default();
+            tree.add_empty_child(format!("{key}: {value}"));
+        }
+        tree.end_child();
+    }
+}
+
+fn stack_json(
     stack: &cf::types::StackSummary,
-    resources: &[cf::types::StackResource],
-) -> ptree::item::StringItem {
-    let mut tree = ptree::TreeBuilder::new(stack.title());
-    add_children(&mut tree, resources);
-    tree.build()
+    description: Option<&cf::types::Stack>,
+    resources: &[cf::types::StackResourceSummary],
+) -> serde_json::Value {
+    let resources = resources
+        .iter()
+        .map(|resource| {
+            serde_json::json!({
+                "logical_id": resource.logical_resource_id(),
+                "physical_id": resource.physical_resource_id(),
+                "type": resource.resource_type(),
+                "status": resource.resource_status().map(|status| status.as_str()),
+            })
+        })
+        .collect::<Vec<_>>();
+    let capabilities = description
+        .and_then(|description| description.capabilities())
+        .unwrap_or_default()
+        .iter()
+        .map(|capability| capability.as_str())
+        .collect::<Vec<_>>();
+    let parameters = description
+        .and_then(|description| description.parameters())
+        .unwrap_or_default()
+        .iter()
+        .map(|parameter| {
+            serde_json::json!({
+                "key": parameter.parameter_key(),
+                "value": parameter.parameter_value(),
+                "resolved_value": parameter.resolved_value(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let outputs = description
+        .and_then(|description| description.outputs())
+        .unwrap_or_default()
+        .iter()
+        .map(|output| {
+            serde_json::json!({
+                "key": output.output_key(),
+                "value": output.output_value(),
+                "export_name": output.export_name(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let tags = description
+        .and_then(|description| description.tags())
+        .unwrap_or_default()
+        .iter()
+        .map(|tag| serde_json::json!({ "key": tag.key(), "value": tag.value() }))
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "id": stack.stack_id(),
+        "name": stack.stack_name(),
+        "status": stack.stack_status().map(|status| status.as_str()),
+        "parent_id": stack.parent_id(),
+        "root_id": stack.root_id(),
+        "description": description.and_then(|description| description.description()),
+        "created": description
+            .and_then(|description| description.creation_time())
+            .and_then(date_time),
+        "last_updated": description
+            .and_then(|description| description.last_updated_time())
+            .and_then(date_time),
+        "termination_protection": description
+            .and_then(|description| description.enable_termination_protection()),
+        "capabilities": capabilities,
+        "parameters": parameters,
+        "outputs": outputs,
+        "tags": tags,
+        "resources": resources,
+    })
+}
+
+fn date_time(date_time: &cf::primitives::DateTime) -> Option<String> {
+    date_time.fmt(cf::primitives::DateTimeFormat::DateTime).ok()
+}
+
+async fn list_stacks(
+    client: &cf::Client,
+    stacks: &[String],
+    statuses: &[StackStatus],
+) -> Result<Vec<cf::types::StackSummary>, cf::Error> {
+    let list_stacks = client.list_stacks();
+    let summaries = statuses
+        .iter()
+        .fold(list_stacks, |list, status| {
+            list.stack_status_filter(status.clone())
+        })
+        .into_paginator()
+        .items()
+        .send()
+        .collect::<Result<Vec<_>, _>>()
+        .await?;
+
+    Ok(select_stacks(summaries, stacks))
+}
+
+/// The requested stacks together with the stacks nested below them
+fn select_stacks(
+    stacks: Vec<cf::types::StackSummary>,
+    requested: &[String],
+) -> Vec<cf::types::StackSummary> {
+    let requested = requested
+        .iter()
+        .map(|s| s.as_str())
+        .map(Some)
+        .collect::<BTreeSet<_>>();
+    if requested.is_empty() {
+        return stacks;
+    }
+    let ids = stacks
+        .iter()
+        .filter(|stack| is_requested(stack, &requested))
+        .filter_map(|stack| stack.stack_id())
+        .map(String::from)
+        .collect::<BTreeSet<_>>();
+    stacks
+        .into_iter()
+        .filter(|stack| {
+            is_requested(stack, &requested)
+                || [stack.root_id(), stack.parent_id()]
+                    .into_iter()
+                    .flatten()
+                    .any(|id| ids.contains(id))
+        })
+        .collect()
 }
 
 fn is_requested(stack: &cf::types::StackSummary, requested: &BTreeSet<Option<&str>>) -> bool {
@@ -118,16 +482,6 @@ fn is_requested(stack: &cf::types::StackSummary, requested: &BTreeSet<Option<&st
         || requested.contains(&stack.stack_id())
 }
 
-fn add_children(ptree: &mut ptree::TreeBuilder, resources: &[cf::types::StackResource]) {
-    resources.iter().for_each(|resource| {
-        ptree.begin_child(resource.title());
-        let r#type = resource.resource_type().unwrap_or("no type");
-        let id = resource.physical_resource_id().unwrap_or("no id");
-        ptree.add_empty_child(format!("{type:40}: {id}"));
-        ptree.end_child();
-    })
-}
-
 pub(crate) fn adjust_stack_statuses(status: Vec<StackStatus>) -> Vec<StackStatus> {
     if status.is_empty() {
         // If no explicit status has been selected get everything but successfully deleted
@@ -188,7 +542,7 @@ impl Title for cf::types::StackSummary {
     }
 }
 
-impl Title for cf::types::StackResource {
+impl Title for cf::types::StackResourceSummary {
     fn title(&self) -> String {
         let name = self.logical_resource_id().unwrap_or_default();
         if let Some(status) = self.resource_status().map(|status| status.as_str()) {
@@ -198,3 +552,99 @@ impl Title for cf::types::StackResource {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arn(name: &str) -> String {
+        format!("arn:aws:cloudformation:us-west-1:123456789012:stack/{name}/0")
+    }
+
+    fn stack(name: &str, parent: Option<&str>) -> cf::types::StackSummary {
+        cf::types::StackSummary::builder()
+            .stack_name(name)
+            .stack_id(arn(name))
+            .set_parent_id(parent.map(arn))
+            .set_root_id(parent.map(arn))
+            .build()
+    }
+
+    fn resource(
+        logical_id: &str,
+        r#type: &str,
+        physical_id: &str,
+    ) -> cf::types::StackResourceSummary {
+        cf::types::StackResourceSummary::builder()
+            .logical_resource_id(logical_id)
+            .resource_type(r#type)
+            .physical_resource_id(physical_id)
+            .build()
+    }
+
+    fn texts(item: &ptree::item::StringItem) -> Vec<&str> {
+        std::iter::once(item.text.as_str())
+            .chain(item.children.iter().flat_map(texts))
+            .collect()
+    }
+
+    #[test]
+    fn select_stacks_keeps_nested_stacks() {
+        let stacks = vec![
+            stack("demo", None),
+            stack("demo-Network-1RZ4GQX0K2M7B", Some("demo")),
+            stack("other", None),
+        ];
+
+        let selected = select_stacks(stacks, &[String::from("demo")]);
+
+        let names = selected
+            .iter()
+            .filter_map(|stack| stack.stack_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["demo", "demo-Network-1RZ4GQX0K2M7B"]);
+    }
+
+    #[test]
+    fn filtered_parent_renders_nested_stack() {
+        let config = aws_types::SdkConfig::builder()
+            .region(aws_types::region::Region::new("us-west-1"))
+            .build();
+        let mut resources = CfResources::new(&config);
+        let stacks = vec![
+            stack("demo", None),
+            stack("demo-Network-1RZ4GQX0K2M7B", Some("demo")),
+        ];
+        resources.resources = select_stacks(stacks, &[String::from("demo")])
+            .into_iter()
+            .map(|stack| {
+                let resource = match stack.parent_id() {
+                    None => resource(
+                        "Network",
+                        NESTED_STACK_TYPE,
+                        &arn("demo-Network-1RZ4GQX0K2M7B"),
+                    ),
+                    Some(_) => resource("Vpc", "AWS::EC2::VPC", "vpc-1"),
+                };
+                (stack, vec![resource])
+            })
+            .collect();
+
+        let trees = resources.trees();
+
+        assert_eq!(trees.len(), 1);
+        assert_eq!(
+            texts(&trees[0]),
+            [
+                "demo",
+                "Network",
+                &format!(
+                    "{NESTED_STACK_TYPE:40}: {}",
+                    arn("demo-Network-1RZ4GQX0K2M7B")
+                ),
+                "Vpc",
+                &format!("{:40}: vpc-1", "AWS::EC2::VPC"),
+            ]
+        );
+    }
+}
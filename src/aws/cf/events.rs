@@ -0,0 +1,292 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use aws_sdk_cloudformation as cf;
+use tokio_stream::StreamExt;
+
+use crate::output::Render;
+
+use super::{list_stacks, StackStatus, Title, NESTED_STACK_TYPE};
+
+#[derive(Debug)]
+pub(crate) struct CfEvents {
+    client: cf::Client,
+    stacks: Vec<cf::types::StackSummary>,
+    /// Events of the stacks and of every nested stack below them by stack id, oldest first
+    events: BTreeMap<String, Vec<cf::types::StackEvent>>,
+}
+
+impl CfEvents {
+    pub(crate) fn new(config: &aws_types::SdkConfig) -> Self {
+        let client = cf::Client::new(config);
+
+        Self {
+            client,
+            stacks: vec![],
+            events: BTreeMap::new(),
+        }
+    }
+
+    /// Nested stacks are walked from their parent when it is selected as well
+    pub(crate) async fn collect_stacks(
+        &mut self,
+        stacks: &[String],
+        statuses: &[StackStatus],
+    ) -> Result<(), cf::Error> {
+        let stacks = list_stacks(&self.client, stacks, statuses).await?;
+        let ids = stacks
+            .iter()
+            .filter_map(|stack| stack.stack_id())
+            .collect::<BTreeSet<_>>();
+        self.stacks = stacks
+            .iter()
+            .filter(|stack| {
+                stack
+                    .parent_id()
+                    .map_or(true, |parent_id| !ids.contains(parent_id))
+            })
+            .cloned()
+            .collect();
+
+        Ok(())
+    }
+
+    pub(crate) async fn collect_events(
+        &mut self,
+        all: bool,
+        progress: &indicatif::ProgressBar,
+    ) -> Result<(), cf::Error> {
+        progress.set_length(self.stacks.len() as u64);
+
+        for stack in self.stacks.clone() {
+            let id = stack.stack_id().unwrap_or_default();
+            progress.set_message(stack.stack_name().unwrap_or(id).to_string());
+            let events = self.collect_stack_events(id, None, !all).await?;
+            // Nested stacks only matter for as long as the operation of their root stack
+            let since = if all {
+                None
+            } else {
+                events.first().and_then(|event| event.timestamp()).copied()
+            };
+            let mut pending = nested_stacks(&events);
+            self.events.insert(id.to_string(), events);
+            progress.inc(1);
+
+            while let Some(nested) = pending.pop() {
+                if self.events.contains_key(&nested) {
+                    continue;
+                }
+                progress.inc_length(1);
+                progress.set_message(nested.clone());
+                let events = self
+                    .collect_stack_events(&nested, since.as_ref(), false)
+                    .await?;
+                pending.extend(nested_stacks(&events));
+                self.events.insert(nested, events);
+                progress.inc(1);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Events come newest first, so stop at `since` or at the start of the latest operation
+    async fn collect_stack_events(
+        &self,
+        stack_id: &str,
+        since: Option<&cf::primitives::DateTime>,
+        latest_operation: bool,
+    ) -> Result<Vec<cf::types::StackEvent>, cf::Error> {
+        let mut stream = self
+            .client
+            .describe_stack_events()
+            .stack_name(stack_id)
+            .into_paginator()
+            .items()
+            .send();
+        let mut events = vec![];
+        while let Some(event) = stream.next().await {
+            let event = event?;
+            if since.map_or(false, |since| {
+                event
+                    .timestamp()
+                    .map_or(false, |timestamp| timestamp < since)
+            }) {
+                break;
+            }
+            let start = latest_operation && is_operation_start(&event);
+            events.push(event);
+            if start {
+                break;
+            }
+        }
+        events.reverse();
+
+        Ok(events)
+    }
+
+    /// The first failure of a stack, followed into the nested stack that caused it
+    fn root_cause(&self, stack_id: &str) -> Option<&cf::types::StackEvent> {
+        let failure = self
+            .events
+            .get(stack_id)?
+            .iter()
+            .find(|event| is_failure(event))?;
+        nested_stack_id(failure)
+            .and_then(|nested| self.root_cause(nested))
+            .or(Some(failure))
+    }
+
+    fn stack_tree(&self, stack: &cf::types::StackSummary) -> ptree::item::StringItem {
+        let id = stack.stack_id().unwrap_or_default();
+        let root_cause = self.root_cause(id);
+        let mut tree = ptree::TreeBuilder::new(stack.title());
+        if let Some(event) = root_cause {
+            let name = event.stack_name().unwrap_or_default();
+            tree.add_empty_child(format!("Root Cause: {name} {}", event_title(event)));
+        }
+        tree.begin_child(String::from("Events"));
+        self.add_events(&mut tree, id, root_cause);
+        tree.end_child();
+        tree.build()
+    }
+
+    /// The events of a nested stack follow the first event of its resource in the parent
+    fn add_events(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        stack_id: &str,
+        root_cause: Option<&cf::types::StackEvent>,
+    ) {
+        let mut nested = BTreeSet::new();
+        for event in self.events.get(stack_id).into_iter().flatten() {
+            let title = event_title(event);
+            if root_cause.map_or(false, |cause| std::ptr::eq(cause, event)) {
+                tree.begin_child(format!("{title} [ROOT CAUSE]"));
+            } else {
+                tree.begin_child(title);
+            }
+            if let Some(nested_id) = nested_stack_id(event) {
+                if self.events.contains_key(nested_id) && nested.insert(nested_id) {
+                    self.add_events(tree, nested_id, root_cause);
+                }
+            }
+            tree.end_child();
+        }
+    }
+
+    fn stack_json(&self, stack: &cf::types::StackSummary) -> serde_json::Value {
+        let id = stack.stack_id().unwrap_or_default();
+        let mut events = vec![];
+        let mut visited = BTreeSet::new();
+        let mut pending = vec![id];
+        while let Some(stack_id) = pending.pop() {
+            if !visited.insert(stack_id) {
+                continue;
+            }
+            for event in self.events.get(stack_id).into_iter().flatten() {
+                pending.extend(nested_stack_id(event));
+                events.push(event);
+            }
+        }
+        events.sort_by_key(|event| event.timestamp());
+        let events = events.into_iter().map(event_json).collect::<Vec<_>>();
+        serde_json::json!({
+            "id": stack.stack_id(),
+            "name": stack.stack_name(),
+            "status": stack.stack_status().map(|status| status.as_str()),
+            "root_cause": self.root_cause(id).map(event_json),
+            "events": events,
+        })
+    }
+}
+
+impl Render for CfEvents {
+    fn trees(&self) -> Vec<ptree::item::StringItem> {
+        self.stacks
+            .iter()
+            .map(|stack| self.stack_tree(stack))
+            .collect()
+    }
+
+    fn json(&self) -> serde_json::Value {
+        let stacks = self
+            .stacks
+            .iter()
+            .map(|stack| self.stack_json(stack))
+            .collect::<Vec<_>>();
+        serde_json::json!({ "stacks": stacks })
+    }
+}
+
+fn event_title(event: &cf::types::StackEvent) -> String {
+    let timestamp = event
+        .timestamp()
+        .and_then(|timestamp| timestamp.fmt(cf::primitives::DateTimeFormat::DateTime).ok())
+        .unwrap_or_default();
+    let name = event.logical_resource_id().unwrap_or_default();
+    let status = event
+        .resource_status()
+        .map_or("no status", |status| status.as_str());
+    let r#type = event.resource_type().unwrap_or("no type");
+    match event.resource_status_reason() {
+        Some(reason) => format!("{timestamp} {name} ({status}) {type}: {reason}"),
+        None => format!("{timestamp} {name} ({status}) {type}"),
+    }
+}
+
+fn event_json(event: &cf::types::StackEvent) -> serde_json::Value {
+    serde_json::json!({
+        "id": event.event_id(),
+        "stack_id": event.stack_id(),
+        "stack_name": event.stack_name(),
+        "timestamp": event
+            .timestamp()
+            .and_then(|timestamp| timestamp.fmt(cf::primitives::DateTimeFormat::DateTime).ok()),
+        "logical_id": event.logical_resource_id(),
+        "physical_id": event.physical_resource_id(),
+        "type": event.resource_type(),
+        "status": event.resource_status().map(|status| status.as_str()),
+        "reason": event.resource_status_reason(),
+    })
+}
+
+/// The stack a nested stack resource event refers to, leaving out the events of the stack itself
+fn nested_stack_id(event: &cf::types::StackEvent) -> Option<&str> {
+    if event.resource_type() != Some(NESTED_STACK_TYPE) {
+        return None;
+    }
+    event
+        .physical_resource_id()
+        .filter(|id| !id.is_empty() && Some(*id) != event.stack_id())
+}
+
+fn nested_stacks(events: &[cf::types::StackEvent]) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(nested_stack_id)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+fn is_failure(event: &cf::types::StackEvent) -> bool {
+    event
+        .resource_status()
+        .map_or(false, |status| status.as_str().ends_with("_FAILED"))
+}
+
+/// A stack operation starts with the stack itself going into progress
+fn is_operation_start(event: &cf::types::StackEvent) -> bool {
+    event.physical_resource_id().is_some()
+        && event.physical_resource_id() == event.stack_id()
+        && matches!(
+            event.resource_status(),
+            Some(
+                cf::types::ResourceStatus::CreateInProgress
+                    | cf::types::ResourceStatus::DeleteInProgress
+                    | cf::types::ResourceStatus::ImportInProgress
+                    | cf::types::ResourceStatus::UpdateInProgress
+            )
+        )
+}
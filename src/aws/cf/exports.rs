@@ -0,0 +1,424 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use aws_sdk_cloudformation as cf;
+use cf::error::ProvideErrorMetadata;
+use tokio_stream::StreamExt;
+
+use crate::aws::Requests;
+use crate::dot;
+use crate::mermaid;
+use crate::output::Render;
+
+use super::{adjust_stack_statuses, list_stacks, Title};
+
+#[derive(Debug)]
+pub(crate) struct CfExports {
+    client: cf::Client,
+    requests: Requests,
+    stacks: Vec<cf::types::StackSummary>,
+    exports: Vec<cf::types::Export>,
+    /// Names of the stacks importing each export by export name
+    imports: BTreeMap<String, Vec<String>>,
+}
+
+impl CfExports {
+    pub(crate) fn new(config: &aws_types::SdkConfig) -> Self {
+        let client = cf::Client::new(config);
+
+        Self {
+            client,
+            requests: Requests::new(),
+            stacks: vec![],
+            exports: vec![],
+            imports: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) async fn collect(
+        &mut self,
+        progress: &indicatif::ProgressBar,
+    ) -> Result<(), cf::Error> {
+        progress.inc_length(1);
+        progress.set_message("Exports");
+        // Importing stacks are only known by name, the stacks map those to their ids
+        let statuses = adjust_stack_statuses(vec![]);
+        let (stacks, exports) = tokio::try_join!(
+            list_stacks(&self.client, &[], &statuses),
+            self.collect_exports(),
+        )?;
+        self.stacks = stacks;
+        self.exports = exports;
+        progress.inc(1);
+        progress.inc_length(self.exports.len() as u64);
+
+        let this = &*self;
+        let names = this.exports.iter().filter_map(|export| export.name());
+        let imports = this
+            .requests
+            .send_each(names, |name| async move {
+                let imports = this.collect_imports(name).await?;
+                progress.set_message(name.to_string());
+                progress.inc(1);
+                Ok::<_, cf::Error>((name.to_string(), imports))
+            })
+            .await?;
+        self.imports = imports.into_iter().collect();
+
+        Ok(())
+    }
+
+    async fn collect_exports(&self) -> Result<Vec<cf::types::Export>, cf::Error> {
+        let exports = self
+            .client
+            .list_exports()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(exports)
+    }
+
+    async fn collect_imports(&self, export_name: &str) -> Result<Vec<String>, cf::Error> {
+        let imports = self
+            .client
+            .list_imports()
+            .export_name(export_name)
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await;
+
+        match imports {
+            Ok(imports) => Ok(imports),
+            // ListImports fails rather than returning nothing for exports no stack imports, with
+            // a ValidationError whose message names the export ("Export 'name' is not imported
+            // by any stack."), so only a ValidationError about this very export counts as none
+            Err(cf::error::SdkError::ServiceError(error))
+                if error.err().code() == Some("ValidationError")
+                    && error.err().message().map_or(false, |message| {
+                        message.contains(&format!("'{export_name}'"))
+                    }) =>
+            {
+                Ok(vec![])
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Stacks are identified by id, falling back to the name for stacks that were not listed
+    fn stack_id<'a>(&'a self, name: &'a str) -> &'a str {
+        self.stacks
+            .iter()
+            .find(|stack| stack.stack_name() == Some(name))
+            .and_then(|stack| stack.stack_id())
+            .unwrap_or(name)
+    }
+
+    fn stack_title(&self, id: &str) -> String {
+        self.stacks
+            .iter()
+            .find(|stack| stack.stack_id() == Some(id))
+            .map_or_else(|| stack_name(id).to_string(), |stack| stack.title())
+    }
+
+    fn importers(&self, export: &cf::types::Export) -> &[String] {
+        export
+            .name()
+            .and_then(|name| self.imports.get(name))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The stacks importing from each exporting stack, by stack id
+    fn dependents(&self) -> BTreeMap<&str, BTreeSet<&str>> {
+        let mut dependents = BTreeMap::<&str, BTreeSet<&str>>::new();
+        for export in &self.exports {
+            let exporter = export.exporting_stack_id().unwrap_or_default();
+            dependents.entry(exporter).or_default();
+            for importer in self.importers(export) {
+                let importer = self.stack_id(importer);
+                dependents.entry(importer).or_default();
+                if importer != exporter {
+                    dependents.entry(exporter).or_default().insert(importer);
+                }
+            }
+        }
+        dependents
+    }
+
+    /// Waves of stacks that can be deleted together once the previous waves are gone,
+    /// followed by the stacks that remain blocked by each other
+    fn delete_order(&self) -> (Vec<Vec<&str>>, Vec<&str>) {
+        let mut dependents = self.dependents();
+        let mut waves = vec![];
+        loop {
+            let wave = dependents
+                .iter()
+                .filter(|(_, importers)| importers.is_empty())
+                .map(|(stack, _)| *stack)
+                .collect::<Vec<_>>();
+            if wave.is_empty() {
+                break;
+            }
+            for stack in &wave {
+                dependents.remove(stack);
+            }
+            for importers in dependents.values_mut() {
+                for stack in &wave {
+                    importers.remove(stack);
+                }
+            }
+            waves.push(wave);
+        }
+        let blocked = dependents.into_keys().collect();
+        (waves, blocked)
+    }
+
+    fn exporting_stacks(&self) -> BTreeMap<&str, Vec<&cf::types::Export>> {
+        let mut stacks = BTreeMap::<&str, Vec<&cf::types::Export>>::new();
+        for export in &self.exports {
+            let exporter = export.exporting_stack_id().unwrap_or_default();
+            stacks.entry(exporter).or_default().push(export);
+        }
+        stacks
+    }
+
+    fn delete_order_tree(&self) -> ptree::item::StringItem {
+        let (waves, blocked) = self.delete_order();
+        let mut tree = ptree::TreeBuilder::new(String::from("Safe Delete Order"));
+        for (index, wave) in waves.iter().enumerate() {
+            let stacks = wave
+                .iter()
+                .map(|stack| stack_name(stack))
+                .collect::<Vec<_>>()
+                .join(", ");
+            tree.add_empty_child(format!("{}: {stacks}", index + 1));
+        }
+        if !blocked.is_empty() {
+            let stacks = blocked
+                .iter()
+                .map(|stack| stack_name(stack))
+                .collect::<Vec<_>>()
+                .join(", ");
+            tree.add_empty_child(format!("Blocked by each other: {stacks}"));
+        }
+        tree.build()
+    }
+
+    fn stack_nodes(&self) -> Vec<&str> {
+        self.dependents().into_keys().collect()
+    }
+
+    fn edges(&self) -> Vec<(&str, &str, &str)> {
+        self.exports
+            .iter()
+            .flat_map(|export| {
+                let exporter = export.exporting_stack_id().unwrap_or_default();
+                let name = export.name().unwrap_or_default();
+                self.importers(export)
+                    .iter()
+                    .map(move |importer| (exporter, self.stack_id(importer), name))
+            })
+            .collect()
+    }
+}
+
+impl Render for CfExports {
+    fn trees(&self) -> Vec<ptree::item::StringItem> {
+        let mut trees = self
+            .exporting_stacks()
+            .into_iter()
+            .map(|(stack, exports)| {
+                let mut tree = ptree::TreeBuilder::new(self.stack_title(stack));
+                for export in exports {
+                    let name = export.name().unwrap_or_default();
+                    let value = export.value().unwrap_or_default();
+                    tree.begin_child(format!("{name}: {value}"));
+                    let importers = self.importers(export);
+                    if importers.is_empty() {
+                        tree.add_empty_child(String::from("not imported"));
+                    }
+                    for importer in importers {
+                        tree.add_empty_child(format!("imported by {importer}"));
+                    }
+                    tree.end_child();
+                }
+                tree.build()
+            })
+            .collect::<Vec<_>>();
+        if !self.exports.is_empty() {
+            trees.push(self.delete_order_tree());
+        }
+        trees
+    }
+
+    fn json(&self) -> serde_json::Value {
+        let exports = self
+            .exports
+            .iter()
+            .map(|export| {
+                let exporter = export.exporting_stack_id();
+                serde_json::json!({
+                    "name": export.name(),
+                    "value": export.value(),
+                    "exporting_stack_id": exporter,
+                    "exporting_stack": exporter.map(stack_name),
+                    "importing_stacks": self.importers(export),
+                })
+            })
+            .collect::<Vec<_>>();
+        let (waves, blocked) = self.delete_order();
+        let delete_order = waves
+            .iter()
+            .map(|wave| {
+                wave.iter()
+                    .map(|stack| stack_name(stack))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let blocked = blocked
+            .iter()
+            .map(|stack| stack_name(stack))
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "exports": exports,
+            "delete_order": delete_order,
+            "blocked": blocked,
+        })
+    }
+
+    fn dot(&self) -> Option<Vec<String>> {
+        let nodes = self
+            .stack_nodes()
+            .into_iter()
+            .map(|stack| dot::node(stack, self.stack_title(stack), "box"));
+        let edges = self
+            .edges()
+            .into_iter()
+            .map(|(exporter, importer, name)| dot::edge(exporter, importer, Some(name)));
+        Some(nodes.chain(edges).collect())
+    }
+
+    fn mermaid(&self) -> Option<Vec<String>> {
+        let nodes = self
+            .stack_nodes()
+            .into_iter()
+            .map(|stack| mermaid::node(stack, self.stack_title(stack)));
+        let edges = self
+            .edges()
+            .into_iter()
+            .map(|(exporter, importer, name)| mermaid::edge(exporter, importer, Some(name)));
+        Some(nodes.chain(edges).collect())
+    }
+}
+
+/// Stack ids are ARNs of the form `arn:aws:cloudformation:region:account:stack/name/uuid`
+fn stack_name(id: &str) -> &str {
+    id.split('/').nth(1).unwrap_or(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arn(name: &str) -> String {
+        format!("arn:aws:cloudformation:us-west-1:123456789012:stack/{name}/0")
+    }
+
+    /// Exports between stacks, each `(exporter, importer)` pair being one export
+    fn exports(imports: &[(&str, &str)]) -> CfExports {
+        let config = aws_types::SdkConfig::builder()
+            .region(aws_types::region::Region::new("us-west-1"))
+            .build();
+        let mut exports = CfExports::new(&config);
+        let names = imports
+            .iter()
+            .flat_map(|(exporter, importer)| [*exporter, *importer])
+            .collect::<BTreeSet<_>>();
+        exports.stacks = names
+            .into_iter()
+            .map(|name| {
+                cf::types::StackSummary::builder()
+                    .stack_name(name)
+                    .stack_id(arn(name))
+                    .build()
+            })
+            .collect();
+        for (exporter, importer) in imports {
+            let name = format!("{exporter}-{importer}");
+            exports.exports.push(
+                cf::types::Export::builder()
+                    .name(&name)
+                    .exporting_stack_id(arn(exporter))
+                    .build(),
+            );
+            exports.imports.insert(name, vec![importer.to_string()]);
+        }
+        exports
+    }
+
+    fn delete_order(exports: &CfExports) -> (Vec<Vec<&str>>, Vec<&str>) {
+        let (waves, blocked) = exports.delete_order();
+        let waves = waves
+            .into_iter()
+            .map(|wave| wave.into_iter().map(stack_name).collect())
+            .collect();
+        let blocked = blocked.into_iter().map(stack_name).collect();
+        (waves, blocked)
+    }
+
+    #[test]
+    fn delete_order_chain() {
+        let exports = exports(&[("a", "b"), ("b", "c")]);
+
+        let (waves, blocked) = delete_order(&exports);
+
+        assert_eq!(waves, [["c"], ["b"], ["a"]]);
+        assert!(blocked.is_empty());
+    }
+
+    #[test]
+    fn delete_order_diamond() {
+        let exports = exports(&[("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")]);
+
+        let (waves, blocked) = delete_order(&exports);
+
+        assert_eq!(waves, [vec!["d"], vec!["b", "c"], vec!["a"]]);
+        assert!(blocked.is_empty());
+    }
+
+    #[test]
+    fn delete_order_self_import() {
+        let exports = exports(&[("a", "a")]);
+
+        let (waves, blocked) = delete_order(&exports);
+
+        assert_eq!(waves, [["a"]]);
+        assert!(blocked.is_empty());
+    }
+
+    #[test]
+    fn delete_order_cycle() {
+        let exports = exports(&[("a", "b"), ("b", "a")]);
+
+        let (waves, blocked) = delete_order(&exports);
+        let tree = exports.delete_order_tree();
+
+        assert!(waves.is_empty());
+        assert_eq!(blocked, ["a", "b"]);
+        let children = tree
+            .children
+            .iter()
+            .map(|child| child.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(children, ["Blocked by each other: a, b"]);
+    }
+
+    #[test]
+    fn stack_name_of_id() {
+        assert_eq!(stack_name(&arn("network")), "network");
+        assert_eq!(stack_name("network"), "network");
+    }
+}
@@ -1,19 +1,55 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 use aws_sdk_ec2 as ec2;
+use clap::ValueEnum;
+use futures::TryFutureExt;
 use tokio_stream::StreamExt;
 
+use crate::output::Render;
 use crate::Show;
 
+use super::autoscaling::AutoScalingResources;
+use super::elb::ElbResources;
+use super::lambda::{self, LambdaResources};
+use super::rds::{self, RdsResources};
+use super::{add_detail, Requests};
+
 use impls::Optionally;
 
+mod asg;
+mod details;
+mod graph;
 mod impls;
+mod layout;
+mod tgw;
+
+/// What to show in the VPC trees besides the resources themselves
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Options {
+    pub(crate) details: bool,
+    pub(crate) rules: bool,
+    pub(crate) layout: Layout,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Layout {
+    /// Group resources by their type
+    Type,
+    /// Nest resources by availability zone and subnet
+    Subnet,
+}
 
 #[derive(Debug)]
 pub(crate) struct Ec2Resources {
     client: ec2::Client,
+    requests: Requests,
     tags: Vec<(String, String)>,
+    options: Options,
+    /// Whether the tags are listed rather than the VPCs
+    list_tags: bool,
     tag_descriptions: Vec<ec2::types::TagDescription>,
+    /// Whether all VPCs are explored rather than the requested ones
+    all_vpcs: bool,
     vpcs: Vec<ec2::types::Vpc>,
     subnets: Vec<ec2::types::Subnet>,                      // 1
     instances: Vec<ec2::types::Instance>,                  // 2
@@ -27,16 +63,52 @@ pub(crate) struct Ec2Resources {
     vpn_connections: Vec<ec2::types::VpnConnection>,       // 10
     vpn_gateways: Vec<ec2::types::VpnGateway>,             // 11
     network_interfaces: Vec<ec2::types::NetworkInterface>, // 12
+    customer_gateways: Vec<ec2::types::CustomerGateway>,   // 13
+    transit_gateways: Vec<ec2::types::TransitGateway>,     // 14
+    elastic_ips: Vec<ec2::types::Address>,                 // 15
+    egress_only_internet_gateways: Vec<ec2::types::EgressOnlyInternetGateway>, // 16
+    carrier_gateways: Vec<ec2::types::CarrierGateway>,     // 17
+    dhcp_options: Vec<ec2::types::DhcpOptions>,            // 18
+    launch_templates: Vec<ec2::types::LaunchTemplate>,     // 19
+    launch_template_versions: Vec<ec2::types::LaunchTemplateVersion>, // 20
+    /// Collected after the instances, for their block devices
+    volumes: Vec<ec2::types::Volume>,
+    snapshots: Vec<ec2::types::Snapshot>,
+    /// Collected after the transit gateways, only for the ones that were kept
+    transit_gateway_attachments: Vec<ec2::types::TransitGatewayAttachment>,
+    transit_gateway_route_tables: Vec<ec2::types::TransitGatewayRouteTable>,
+    transit_gateway_associations:
+        HashMap<String, Vec<ec2::types::TransitGatewayRouteTableAssociation>>,
+    transit_gateway_propagations:
+        HashMap<String, Vec<ec2::types::TransitGatewayRouteTablePropagation>>,
+    load_balancers: ElbResources,
+    databases: RdsResources,
+    functions: LambdaResources,
+    auto_scaling: AutoScalingResources,
+    /// Resources that could not be collected without failing the region
+    warnings: Vec<String>,
 }
 
 impl Ec2Resources {
-    pub(crate) fn new(config: &aws_types::SdkConfig, tags: &[(String, String)]) -> Self {
+    pub(crate) fn new(
+        config: &aws_types::SdkConfig,
+        tags: &[(String, String)],
+        options: Options,
+    ) -> Self {
         let client = ec2::Client::new(config);
+        let load_balancers = ElbResources::new(config, tags);
+        let databases = RdsResources::new(config, tags);
+        let functions = LambdaResources::new(config);
+        let auto_scaling = AutoScalingResources::new(config);
         let tags = tags.to_vec();
         Self {
             client,
+            requests: Requests::new(),
             tags,
+            options,
+            list_tags: false,
             tag_descriptions: vec![],
+            all_vpcs: true,
             vpcs: vec![],
             subnets: vec![],
             instances: vec![],
@@ -50,6 +122,25 @@ impl Ec2Resources {
             vpn_connections: vec![],
             vpn_gateways: vec![],
             network_interfaces: vec![],
+            customer_gateways: vec![],
+            transit_gateway_attachments: vec![],
+            transit_gateways: vec![],
+            transit_gateway_route_tables: vec![],
+            elastic_ips: vec![],
+            egress_only_internet_gateways: vec![],
+            carrier_gateways: vec![],
+            dhcp_options: vec![],
+            volumes: vec![],
+            launch_templates: vec![],
+            launch_template_versions: vec![],
+            snapshots: vec![],
+            transit_gateway_associations: HashMap::new(),
+            transit_gateway_propagations: HashMap::new(),
+            load_balancers,
+            databases,
+            functions,
+            auto_scaling,
+            warnings: vec![],
         }
     }
 
@@ -57,41 +148,141 @@ impl Ec2Resources {
         &mut self,
         progress: &indicatif::ProgressBar,
     ) -> Result<(), ec2::Error> {
-        progress.set_length(12);
+        progress.inc_length(22);
+        progress.set_message("Resources");
 
+        // All the DescribeX calls are independent, so run them at the same time
+        let this = &*self;
         macro_rules! collect {
-            ($collector:ident, $title:expr) => {{
-                progress.set_message($title);
-                self.$collector().await?;
-                progress.inc(1);
-            }};
+            ($collector:ident, $title:expr) => {
+                async {
+                    let resources = this.$collector().await?;
+                    progress.set_message($title);
+                    progress.inc(1);
+                    Ok::<_, ec2::Error>(resources)
+                }
+            };
         }
-        collect!(collect_subnets, "Subnets");
-        collect!(collect_instances, "Instances");
-        collect!(collect_internet_gateways, "Internet Gateways");
-        collect!(collect_route_tables, "Route Tables");
-        collect!(collect_network_acls, "Network ACLs");
-        collect!(collect_vpc_peerings, "VPC Peerings");
-        collect!(collect_vpc_endpoints, "VPC Endpoints");
-        collect!(collect_nat_gateways, "NAT Gateways");
-        collect!(collect_security_groups, "Security Groups");
-        collect!(collect_vpn_connections, "VPN Connections");
-        collect!(collect_vpn_gateways, "VPN Gateways");
-        collect!(collect_network_interfaces, "Network Interfaces");
+        let (
+            subnets,
+            instances,
+            internet_gateways,
+            route_tables,
+            network_acls,
+            vpc_peerings,
+            vpc_endpoints,
+            nat_gateways,
+            security_groups,
+            vpn_connections,
+            vpn_gateways,
+            network_interfaces,
+            customer_gateways,
+            transit_gateways,
+            elastic_ips,
+            egress_only_internet_gateways,
+            carrier_gateways,
+            dhcp_options,
+            launch_templates,
+            launch_template_versions,
+        ) = tokio::try_join!(
+            collect!(collect_subnets, "Subnets"),
+            collect!(collect_instances, "Instances"),
+            collect!(collect_internet_gateways, "Internet Gateways"),
+            collect!(collect_route_tables, "Route Tables"),
+            collect!(collect_network_acls, "Network ACLs"),
+            collect!(collect_vpc_peerings, "VPC Peerings"),
+            collect!(collect_vpc_endpoints, "VPC Endpoints"),
+            collect!(collect_nat_gateways, "NAT Gateways"),
+            collect!(collect_security_groups, "Security Groups"),
+            collect!(collect_vpn_connections, "VPN Connections"),
+            collect!(collect_vpn_gateways, "VPN Gateways"),
+            collect!(collect_network_interfaces, "Network Interfaces"),
+            collect!(collect_customer_gateways, "Customer Gateways"),
+            collect!(collect_transit_gateways, "Transit Gateways"),
+            collect!(collect_elastic_ips, "Elastic IPs"),
+            collect!(
+                collect_egress_only_internet_gateways,
+                "Egress-only Internet Gateways"
+            ),
+            collect!(collect_carrier_gateways, "Carrier Gateways"),
+            collect!(collect_dhcp_options, "DHCP Options"),
+            collect!(collect_launch_templates, "Launch Templates"),
+            collect!(collect_launch_template_versions, "Launch Template Versions"),
+        )?;
+
+        self.subnets = subnets;
+        self.instances = instances;
+        self.internet_gateways = internet_gateways;
+        self.route_tables = route_tables;
+        self.network_acls = network_acls;
+        self.vpc_peerings = vpc_peerings;
+        self.vpc_endpoints = vpc_endpoints;
+        self.nat_gateways = nat_gateways;
+        self.security_groups = security_groups;
+        self.vpn_connections = vpn_connections;
+        self.vpn_gateways = vpn_gateways;
+        self.network_interfaces = network_interfaces;
+        self.customer_gateways = customer_gateways;
+        self.transit_gateways = transit_gateways;
+        self.elastic_ips = elastic_ips;
+        self.egress_only_internet_gateways = egress_only_internet_gateways;
+        self.carrier_gateways = carrier_gateways;
+        self.dhcp_options = dhcp_options;
+        self.launch_templates = launch_templates;
+        self.launch_template_versions = launch_template_versions;
+
+        self.volumes = self.collect_volumes().await?;
+        progress.set_message("Volumes");
+        progress.inc(1);
+        self.snapshots = self.collect_snapshots().await?;
+        progress.set_message("Snapshots");
+        progress.inc(1);
+
+        self.collect_transit_gateway_details(progress).await?;
 
         Ok(())
     }
 
-    pub(crate) fn trees(&self) -> impl Iterator<Item = ptree::item::StringItem> + '_ {
-        if self.tag_descriptions.is_empty() {
-            self.vpcs()
-                .iter()
-                .map(|vpc| self.vpc_tree(vpc))
-                .collect::<Vec<_>>()
-                .into_iter()
-        } else {
-            vec![self.tag_tree()].into_iter()
+    /// Load balancers, databases, Lambda functions and Auto Scaling groups come from other
+    /// APIs and only add to the VPC tree, so lacking the permission to list them is not fatal
+    pub(crate) async fn collect_attached(
+        &mut self,
+        progress: &indicatif::ProgressBar,
+    ) -> anyhow::Result<()> {
+        if self.vpcs.is_empty() {
+            return Ok(());
         }
+        let vpc_ids = self.vpcs().iter().map(|vpc| vpc.id()).collect::<Vec<_>>();
+        let (load_balancers, databases, functions, auto_scaling) = tokio::join!(
+            self.load_balancers.collect(&vpc_ids, progress),
+            self.databases
+                .collect_databases(&vpc_ids, progress)
+                .err_into::<anyhow::Error>(),
+            self.functions
+                .collect_functions(&vpc_ids, progress)
+                .err_into::<anyhow::Error>(),
+            self.auto_scaling
+                .collect(progress)
+                .err_into::<anyhow::Error>(),
+        );
+
+        for (resources, result) in [
+            ("load balancers", load_balancers),
+            ("databases", databases),
+            ("Lambda functions", functions),
+            ("Auto Scaling groups", auto_scaling),
+        ] {
+            if let Err(error) = result {
+                if super::is_access_denied(&error) {
+                    self.warnings
+                        .push(format!("access denied, leaving out {resources}"));
+                } else {
+                    return Err(error);
+                }
+            }
+        }
+
+        Ok(())
     }
 
     fn tag_tree(&self) -> ptree::item::StringItem {
@@ -132,21 +323,318 @@ impl Ec2Resources {
         let mut tree = ptree::TreeBuilder::new(vpc.id_and_name());
         let tree = &mut tree;
         let vpc_id = vpc.id();
-        add_children(tree, "Subnets", self.subnets(&vpc_id));
-        add_children(tree, "Instances", self.instances(&vpc_id));
+        let by_type = self.options.layout == Layout::Type;
+        if by_type {
+            add_children_with(tree, "Subnets", self.subnets(&vpc_id), |tree, subnet| {
+                self.add_functions(tree, self.functions.subnet_functions(&subnet.id()));
+            });
+            self.add_instances(tree, self.instances(&vpc_id));
+        } else {
+            self.add_zones(tree, &vpc_id);
+        }
         add_children(tree, "Internet Gateways", self.internet_gateways(&vpc_id));
-        add_children(tree, "Route Tables", self.route_tables(&vpc_id));
+        add_children(
+            tree,
+            "Egress-only Internet Gateways",
+            self.egress_only_internet_gateways(&vpc_id),
+        );
+        add_children(tree, "Carrier Gateways", self.carrier_gateways(&vpc_id));
+        add_children_with(
+            tree,
+            "Route Tables",
+            self.route_tables(&vpc_id),
+            |tree, route_table| self.route_table_details(tree, route_table),
+        );
         add_children(tree, "Network ACLs", self.network_acls(&vpc_id));
-        add_children(tree, "VPC Peering Connections", self.vpc_peerings(&vpc_id));
-        add_children(tree, "VPC Endpoints", self.vpc_endpoints(&vpc_id));
-        add_children(tree, "NAT Gateways", self.nat_gateways(&vpc_id));
-        add_children(tree, "Security Groups", self.security_groups(&vpc_id));
-        add_children(tree, "VPN Connections", self.vpn_connections(&vpc_id));
+        add_children_with(
+            tree,
+            "VPC Peering Connections",
+            self.vpc_peerings(&vpc_id),
+            |tree, vpc_peering| details::vpc_peering(tree, vpc_peering, &vpc_id),
+        );
+        if by_type {
+            add_children(tree, "VPC Endpoints", self.vpc_endpoints(&vpc_id));
+            add_children(tree, "NAT Gateways", self.nat_gateways(&vpc_id));
+        } else {
+            // Gateway endpoints live in route tables rather than in subnets
+            add_children(
+                tree,
+                "VPC Endpoints",
+                self.vpc_endpoints(&vpc_id)
+                    .into_iter()
+                    .filter(|endpoint| endpoint.subnet_ids().unwrap_or_default().is_empty())
+                    .collect(),
+            );
+        }
+        add_children_with(
+            tree,
+            "Security Groups",
+            self.security_groups(&vpc_id),
+            |tree, security_group| {
+                if let Some(eks) =
+                    details::eks_membership(security_group.tags().unwrap_or_default())
+                {
+                    tree.add_empty_child(eks);
+                }
+                self.add_functions(
+                    tree,
+                    self.functions
+                        .security_group_functions(&security_group.id()),
+                );
+                if self.options.rules {
+                    self.security_group_rules(tree, security_group);
+                }
+            },
+        );
+        add_children_with(
+            tree,
+            "VPN Connections",
+            self.vpn_connections(&vpc_id),
+            |tree, vpn_connection| {
+                if let Some(customer_gateway) = self.customer_gateway(vpn_connection) {
+                    let ip_address = customer_gateway.ip_address().unwrap_or("no address");
+                    tree.add_empty_child(format!(
+                        "Customer Gateway: {} [{ip_address}]",
+                        customer_gateway.id_and_name()
+                    ));
+                }
+            },
+        );
         add_children(tree, "VPN Gateways", self.vpn_gateways(&vpc_id));
-        add_children(tree, "Network Interfaces", self.network_interfaces(&vpc_id));
+        if by_type {
+            self.add_databases(tree, self.databases.instances_of(&vpc_id));
+        } else {
+            self.add_databases(
+                tree,
+                self.databases
+                    .instances_of(&vpc_id)
+                    .into_iter()
+                    .filter(|database| self.database_subnet(database).is_none())
+                    .collect(),
+            );
+        }
+        self.load_balancers.add_vpc_load_balancers(tree, &vpc_id);
+        if by_type {
+            self.add_network_interfaces(tree, self.network_interfaces(&vpc_id));
+        }
+        add_children_with(
+            tree,
+            "Elastic IPs",
+            self.elastic_ips(&vpc_id),
+            details::elastic_ip,
+        );
+        add_children_with(
+            tree,
+            "DHCP Options",
+            self.dhcp_options(&vpc_id),
+            details::dhcp_options,
+        );
+        add_children_with(
+            tree,
+            "Transit Gateway Attachments",
+            self.transit_gateway_attachments(&vpc_id),
+            |tree, attachment| {
+                let transit_gateway = attachment
+                    .transit_gateway_id()
+                    .map(|transit_gateway_id| self.transit_gateway_title(transit_gateway_id));
+                add_detail(tree, "Transit Gateway", transit_gateway.as_deref());
+            },
+        );
         tree.build()
     }
 
+    fn add_network_interfaces(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        network_interfaces: Vec<&ec2::types::NetworkInterface>,
+    ) {
+        add_children_with(
+            tree,
+            "Network Interfaces",
+            network_interfaces,
+            |tree, network_interface| {
+                if let Some(eks) = self.network_interface_eks(network_interface) {
+                    tree.add_empty_child(eks);
+                }
+                let functions = self.network_interface_functions(network_interface);
+                if !functions.is_empty() {
+                    tree.add_empty_child(format!("Lambda: {}", functions.join(", ")));
+                }
+            },
+        );
+    }
+
+    fn network_interface_json(
+        &self,
+        network_interface: &ec2::types::NetworkInterface,
+    ) -> serde_json::Value {
+        json_with(
+            network_interface,
+            serde_json::json!({
+                "type": network_interface
+                    .interface_type()
+                    .map(|r#type| r#type.as_str()),
+                "subnet_id": network_interface.subnet_id(),
+                "private_ip": network_interface.private_ip_address(),
+                "eks": self.network_interface_eks(network_interface),
+                "lambda_functions": self.network_interface_functions(network_interface),
+            }),
+        )
+    }
+
+    /// Lambda interfaces are shared by the functions with the same subnet and security groups
+    fn network_interface_functions(
+        &self,
+        network_interface: &ec2::types::NetworkInterface,
+    ) -> Vec<String> {
+        if network_interface.interface_type() != Some(&ec2::types::NetworkInterfaceType::Lambda) {
+            return vec![];
+        }
+        let group_ids = network_interface
+            .groups()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|group| group.group_id())
+            .collect::<Vec<_>>();
+        self.functions
+            .network_interface_functions(network_interface.subnet_id(), &group_ids)
+            .into_iter()
+            .map(|function| function.id())
+            .collect()
+    }
+
+    fn add_functions(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        functions: Vec<&aws_sdk_lambda::types::FunctionConfiguration>,
+    ) {
+        add_children_with(tree, "Lambda Functions", functions, |tree, function| {
+            if self.options.details {
+                lambda::add_function_details(tree, function);
+            }
+        });
+    }
+
+    /// EKS rarely tags the interfaces it creates, so also look at the description of the
+    /// control plane interfaces and at the tags of the instance an interface is attached to
+    fn network_interface_eks(
+        &self,
+        network_interface: &ec2::types::NetworkInterface,
+    ) -> Option<String> {
+        details::eks_membership(network_interface.tag_set().unwrap_or_default())
+            .or_else(|| {
+                network_interface
+                    .description()
+                    .and_then(|description| description.strip_prefix("Amazon EKS "))
+                    .map(|cluster| format!("EKS: {cluster}"))
+            })
+            .or_else(|| {
+                let instance_id = network_interface
+                    .attachment()
+                    .and_then(|attachment| attachment.instance_id())?;
+                let instance = self
+                    .instances
+                    .iter()
+                    .find(|instance| instance.instance_id() == Some(instance_id))?;
+                details::eks_membership(instance.tags().unwrap_or_default())
+            })
+    }
+
+    fn add_databases(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        databases: Vec<&aws_sdk_rds::types::DbInstance>,
+    ) {
+        add_children_with(tree, "Databases", databases, |tree, database| {
+            if self.options.layout == Layout::Type {
+                let subnet = self
+                    .database_subnet(database)
+                    .map(|subnet_id| self.subnet_title(subnet_id));
+                add_detail(tree, "Subnet", subnet.as_deref());
+            }
+            if self.options.details {
+                rds::add_instance_details(tree, database);
+            }
+        });
+    }
+
+    fn vpc_json(&self, vpc: &ec2::types::Vpc) -> serde_json::Value {
+        let vpc_id = vpc.id();
+        let instances = self
+            .instances(&vpc_id)
+            .into_iter()
+            .map(|instance| {
+                let instance_id = instance.instance_id().unwrap_or_default();
+                let volumes =
+                    self.instance_volumes_of(instance_id)
+                        .into_iter()
+                        .map(|volume| {
+                            let attachment =
+                                volume.attachments().unwrap_or_default().iter().find(
+                                    |attachment| attachment.instance_id() == Some(instance_id),
+                                );
+                            self.volume_json(volume, attachment)
+                        })
+                        .collect::<Vec<_>>();
+                let mut json = details::instance_json(instance);
+                json["volumes"] = serde_json::json!(volumes);
+                json
+            })
+            .collect::<Vec<_>>();
+        let route_tables = self
+            .route_tables(&vpc_id)
+            .into_iter()
+            .map(|route_table| self.route_table_json(route_table))
+            .collect::<Vec<_>>();
+        let vpc_peerings = self
+            .vpc_peerings(&vpc_id)
+            .into_iter()
+            .map(|vpc_peering| details::vpc_peering_json(vpc_peering, &vpc_id))
+            .collect::<Vec<_>>();
+        let security_groups = self
+            .security_groups(&vpc_id)
+            .into_iter()
+            .map(|security_group| self.security_group_json(security_group))
+            .collect::<Vec<_>>();
+        let network_interfaces = self
+            .network_interfaces(&vpc_id)
+            .into_iter()
+            .map(|network_interface| self.network_interface_json(network_interface))
+            .collect::<Vec<_>>();
+        let transit_gateway_attachments = self.transit_gateway_attachments(&vpc_id);
+        let egress_only_internet_gateways = self.egress_only_internet_gateways(&vpc_id);
+        serde_json::json!({
+            "id": vpc_id,
+            "name": vpc.name(),
+            "subnets": json_children(self.subnets(&vpc_id)),
+            "instances": instances,
+            "internet_gateways": json_children(self.internet_gateways(&vpc_id)),
+            "route_tables": route_tables,
+            "network_acls": json_children(self.network_acls(&vpc_id)),
+            "vpc_peering_connections": vpc_peerings,
+            "vpc_endpoints": json_children(self.vpc_endpoints(&vpc_id)),
+            "nat_gateways": json_children(self.nat_gateways(&vpc_id)),
+            "security_groups": security_groups,
+            "vpn_connections": json_children(self.vpn_connections(&vpc_id)),
+            "vpn_gateways": json_children(self.vpn_gateways(&vpc_id)),
+            "customer_gateways": json_children(self.customer_gateways(&vpc_id)),
+            "transit_gateway_attachments": json_children(transit_gateway_attachments),
+            "elastic_ips": json_children(self.elastic_ips(&vpc_id)),
+            "egress_only_internet_gateways": json_children(egress_only_internet_gateways),
+            "carrier_gateways": json_children(self.carrier_gateways(&vpc_id)),
+            "dhcp_options": json_children(self.dhcp_options(&vpc_id)),
+            "load_balancers": self.load_balancers.vpc_json(&vpc_id),
+            "databases": json_children(self.databases.instances_of(&vpc_id)),
+            "lambda_functions": json_children(self.functions.functions_of(&vpc_id)),
+            "auto_scaling_groups": self
+                .auto_scaling_groups(&vpc_id)
+                .into_iter()
+                .map(|group| self.auto_scaling_group_json(group))
+                .collect::<Vec<_>>(),
+            "network_interfaces": network_interfaces,
+        })
+    }
+
     fn vpcs(&self) -> &[ec2::types::Vpc] {
         &self.vpcs
     }
@@ -201,10 +689,12 @@ impl Ec2Resources {
         self.vpc_peerings
             .iter()
             .filter(|vpc_peering| {
-                vpc_peering
-                    .requester_vpc_info()
-                    .map(|info| info.vpc_id() == vpc_id)
-                    .unwrap_or_default()
+                let requester = vpc_peering.requester_vpc_info();
+                let accepter = vpc_peering.accepter_vpc_info();
+                requester
+                    .into_iter()
+                    .chain(accepter)
+                    .any(|info| info.vpc_id() == vpc_id)
             })
             .collect()
     }
@@ -233,19 +723,209 @@ impl Ec2Resources {
             .collect()
     }
 
-    fn vpn_connections(&self, _vpc_id: impl AsRef<str>) -> Vec<&ec2::types::VpnConnection> {
-        // let vpc_id = Some(vpc_id.as_ref());
+    /// VPN connections terminating on a VPN gateway attached to this VPC,
+    /// or on a transit gateway this VPC is attached to
+    fn vpn_connections(&self, vpc_id: impl AsRef<str>) -> Vec<&ec2::types::VpnConnection> {
+        let vpn_gateways = self
+            .vpn_gateways(&vpc_id)
+            .into_iter()
+            .filter_map(|vpn_gateway| vpn_gateway.vpn_gateway_id())
+            .collect::<Vec<_>>();
+        let transit_gateways = self.transit_gateway_ids(&vpc_id);
         self.vpn_connections
             .iter()
-            // .filter(|vpn_connection| vpn_connection.vpc_id() == Some(vpc_id))
+            .filter(|vpn_connection| {
+                vpn_connection
+                    .vpn_gateway_id()
+                    .map_or(false, |id| vpn_gateways.contains(&id))
+                    || vpn_connection
+                        .transit_gateway_id()
+                        .map_or(false, |id| transit_gateways.contains(&id))
+            })
             .collect()
     }
 
-    fn vpn_gateways(&self, _vpc_id: impl AsRef<str>) -> Vec<&ec2::types::VpnGateway> {
-        // let vpc_id = Some(vpc_id.as_ref());
+    fn vpn_gateways(&self, vpc_id: impl AsRef<str>) -> Vec<&ec2::types::VpnGateway> {
+        let vpc_id = Some(vpc_id.as_ref());
         self.vpn_gateways
             .iter()
-            // .filter(|vpn_gateway| vpn_gateway.vpc_id() == Some(vpc_id))
+            .filter(|vpn_gateway| {
+                vpn_gateway
+                    .vpc_attachments()
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|attachment| {
+                        attachment.state() != Some(&ec2::types::AttachmentStatus::Detached)
+                    })
+                    .any(|attachment| attachment.vpc_id() == vpc_id)
+            })
+            .collect()
+    }
+
+    fn customer_gateways(&self, vpc_id: impl AsRef<str>) -> Vec<&ec2::types::CustomerGateway> {
+        let mut customer_gateways = self
+            .vpn_connections(vpc_id)
+            .into_iter()
+            .filter_map(|vpn_connection| self.customer_gateway(vpn_connection))
+            .collect::<Vec<_>>();
+        // Several VPN connections may share the same customer gateway
+        customer_gateways.sort_by_key(|customer_gateway| customer_gateway.customer_gateway_id());
+        customer_gateways.dedup_by_key(|customer_gateway| customer_gateway.customer_gateway_id());
+        customer_gateways
+    }
+
+    fn customer_gateway(
+        &self,
+        vpn_connection: &ec2::types::VpnConnection,
+    ) -> Option<&ec2::types::CustomerGateway> {
+        let customer_gateway_id = vpn_connection.customer_gateway_id()?;
+        self.customer_gateways.iter().find(|customer_gateway| {
+            customer_gateway.customer_gateway_id() == Some(customer_gateway_id)
+        })
+    }
+
+    fn transit_gateway_attachments(
+        &self,
+        vpc_id: impl AsRef<str>,
+    ) -> Vec<&ec2::types::TransitGatewayAttachment> {
+        let vpc_id = Some(vpc_id.as_ref());
+        self.transit_gateway_attachments
+            .iter()
+            .filter(|attachment| {
+                attachment.resource_type()
+                    == Some(&ec2::types::TransitGatewayAttachmentResourceType::Vpc)
+            })
+            .filter(|attachment| attachment.resource_id() == vpc_id)
+            .collect()
+    }
+
+    fn transit_gateway_ids(&self, vpc_id: impl AsRef<str>) -> Vec<&str> {
+        self.transit_gateway_attachments(vpc_id)
+            .into_iter()
+            .filter_map(|attachment| attachment.transit_gateway_id())
+            .collect()
+    }
+
+    /// Elastic IPs carry no VPC of their own, only the instance or interface they are
+    /// associated with
+    fn elastic_ips(&self, vpc_id: impl AsRef<str>) -> Vec<&ec2::types::Address> {
+        let vpc_id = vpc_id.as_ref();
+        let instances = self
+            .instances(vpc_id)
+            .into_iter()
+            .filter_map(|instance| instance.instance_id())
+            .collect::<Vec<_>>();
+        let network_interfaces = self
+            .network_interfaces(vpc_id)
+            .into_iter()
+            .filter_map(|network_interface| network_interface.network_interface_id())
+            .collect::<Vec<_>>();
+        self.elastic_ips
+            .iter()
+            .filter(|address| {
+                address
+                    .network_interface_id()
+                    .map_or(false, |id| network_interfaces.contains(&id))
+                    || address
+                        .instance_id()
+                        .map_or(false, |id| instances.contains(&id))
+            })
+            .collect()
+    }
+
+    /// The subnet group only narrows a database down to one subnet per zone, unless several of
+    /// its subnets share the zone; then the subnet holding an RDS network interface wins
+    fn database_subnet<'a>(&self, database: &'a aws_sdk_rds::types::DbInstance) -> Option<&'a str> {
+        let candidates = rds::instance_subnet_candidates(database);
+        if let [subnet_id] = candidates.as_slice() {
+            return Some(subnet_id);
+        }
+        candidates.into_iter().find(|subnet_id| {
+            self.network_interfaces.iter().any(|eni| {
+                eni.subnet_id() == Some(subnet_id) && eni.requester_id() == Some("amazon-rds")
+            })
+        })
+    }
+
+    /// Unassociated Elastic IPs and unattached volumes belong to the region rather than to a VPC,
+    /// so they are only shown when exploring all of the region's VPCs
+    fn unassociated_elastic_ips(&self) -> Vec<&ec2::types::Address> {
+        if !self.all_vpcs {
+            return vec![];
+        }
+        self.elastic_ips
+            .iter()
+            .filter(|address| address.association_id().is_none())
+            .collect()
+    }
+
+    fn egress_only_internet_gateways(
+        &self,
+        vpc_id: impl AsRef<str>,
+    ) -> Vec<&ec2::types::EgressOnlyInternetGateway> {
+        let vpc_id = Some(vpc_id.as_ref());
+        self.egress_only_internet_gateways
+            .iter()
+            .filter(|eigw| {
+                eigw.attachments()
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|attachment| attachment.vpc_id() == vpc_id)
+            })
+            .collect()
+    }
+
+    fn carrier_gateways(&self, vpc_id: impl AsRef<str>) -> Vec<&ec2::types::CarrierGateway> {
+        let vpc_id = Some(vpc_id.as_ref());
+        self.carrier_gateways
+            .iter()
+            .filter(|carrier_gateway| carrier_gateway.vpc_id() == vpc_id)
+            .collect()
+    }
+
+    fn dhcp_options(&self, vpc_id: impl AsRef<str>) -> Vec<&ec2::types::DhcpOptions> {
+        let vpc_id = Some(vpc_id.as_ref());
+        let dhcp_options_id = self
+            .vpcs
+            .iter()
+            .find(|vpc| vpc.vpc_id() == vpc_id)
+            .and_then(|vpc| vpc.dhcp_options_id());
+        self.dhcp_options
+            .iter()
+            .filter(|dhcp_options| {
+                dhcp_options_id.is_some() && dhcp_options.dhcp_options_id() == dhcp_options_id
+            })
+            .collect()
+    }
+
+    fn instance_volumes_of(&self, instance_id: &str) -> Vec<&ec2::types::Volume> {
+        let instance_id = Some(instance_id);
+        self.volumes
+            .iter()
+            .filter(|volume| {
+                volume
+                    .attachments()
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|attachment| attachment.instance_id() == instance_id)
+            })
+            .collect()
+    }
+
+    fn volume_snapshots_of(&self, volume_id: Option<&str>) -> Vec<&ec2::types::Snapshot> {
+        self.snapshots
+            .iter()
+            .filter(|snapshot| volume_id.is_some() && snapshot.volume_id() == volume_id)
+            .collect()
+    }
+
+    fn unattached_volumes(&self) -> Vec<&ec2::types::Volume> {
+        if !self.all_vpcs {
+            return vec![];
+        }
+        self.volumes
+            .iter()
+            .filter(|volume| volume.attachments().unwrap_or_default().is_empty())
             .collect()
     }
 
@@ -261,6 +941,7 @@ impl Ec2Resources {
         &mut self,
         _progress: &indicatif::ProgressBar,
     ) -> Result<(), ec2::Error> {
+        self.list_tags = true;
         self.tag_descriptions = self
             .client
             .describe_tags()
@@ -282,6 +963,7 @@ impl Ec2Resources {
     }
 
     pub(crate) async fn collect_vpcs(&mut self, vpcs: &[String]) -> Result<(), ec2::Error> {
+        self.all_vpcs = vpcs.is_empty();
         self.vpcs = vpcs
             .iter()
             .map(|vpc_id| ec2::types::Vpc::builder().vpc_id(vpc_id).build())
@@ -301,8 +983,8 @@ impl Ec2Resources {
         Ok(())
     }
 
-    async fn collect_subnets(&mut self) -> Result<(), ec2::Error> {
-        self.subnets = self
+    async fn collect_subnets(&self) -> Result<Vec<ec2::types::Subnet>, ec2::Error> {
+        let subnets = self
             .client
             .describe_subnets()
             .optionally_filter(self.vpc_filter())
@@ -313,11 +995,11 @@ impl Ec2Resources {
             .collect::<Result<_, _>>()
             .await?;
 
-        Ok(())
+        Ok(subnets)
     }
 
-    async fn collect_instances(&mut self) -> Result<(), ec2::Error> {
-        self.instances = self
+    async fn collect_instances(&self) -> Result<Vec<ec2::types::Instance>, ec2::Error> {
+        let instances = self
             .client
             .describe_instances()
             .optionally_filter(self.vpc_filter())
@@ -331,11 +1013,13 @@ impl Ec2Resources {
             .flat_map(|reservation| reservation.instances.unwrap_or_default())
             .collect();
 
-        Ok(())
+        Ok(instances)
     }
 
-    async fn collect_internet_gateways(&mut self) -> Result<(), ec2::Error> {
-        self.internet_gateways = self
+    async fn collect_internet_gateways(
+        &self,
+    ) -> Result<Vec<ec2::types::InternetGateway>, ec2::Error> {
+        let internet_gateways = self
             .client
             .describe_internet_gateways()
             .optionally_filter(self.attachment_vpc_filter())
@@ -346,11 +1030,11 @@ impl Ec2Resources {
             .collect::<Result<_, _>>()
             .await?;
 
-        Ok(())
+        Ok(internet_gateways)
     }
 
-    async fn collect_route_tables(&mut self) -> Result<(), ec2::Error> {
-        self.route_tables = self
+    async fn collect_route_tables(&self) -> Result<Vec<ec2::types::RouteTable>, ec2::Error> {
+        let route_tables = self
             .client
             .describe_route_tables()
             .optionally_filter(self.vpc_filter())
@@ -361,11 +1045,11 @@ impl Ec2Resources {
             .collect::<Result<_, _>>()
             .await?;
 
-        Ok(())
+        Ok(route_tables)
     }
 
-    async fn collect_network_acls(&mut self) -> Result<(), ec2::Error> {
-        self.network_acls = self
+    async fn collect_network_acls(&self) -> Result<Vec<ec2::types::NetworkAcl>, ec2::Error> {
+        let network_acls = self
             .client
             .describe_network_acls()
             .optionally_filter(self.vpc_filter())
@@ -376,14 +1060,39 @@ impl Ec2Resources {
             .collect::<Result<_, _>>()
             .await?;
 
-        Ok(())
+        Ok(network_acls)
+    }
+
+    async fn collect_vpc_peerings(
+        &self,
+    ) -> Result<Vec<ec2::types::VpcPeeringConnection>, ec2::Error> {
+        let mut vpc_peerings = self
+            .describe_vpc_peerings(self.requester_vpc_filter())
+            .await?;
+
+        // Filters are ANDed, so the accepter side needs a call of its own
+        if let Some(filter) = self.accepter_vpc_filter() {
+            let accepted = self.describe_vpc_peerings(Some(filter)).await?;
+            vpc_peerings.extend(accepted);
+            vpc_peerings.sort_by(|a, b| {
+                a.vpc_peering_connection_id()
+                    .cmp(&b.vpc_peering_connection_id())
+            });
+            vpc_peerings
+                .dedup_by(|a, b| a.vpc_peering_connection_id() == b.vpc_peering_connection_id());
+        }
+
+        Ok(vpc_peerings)
     }
 
-    async fn collect_vpc_peerings(&mut self) -> Result<(), ec2::Error> {
-        self.vpc_peerings = self
+    async fn describe_vpc_peerings(
+        &self,
+        filter: Option<ec2::types::Filter>,
+    ) -> Result<Vec<ec2::types::VpcPeeringConnection>, ec2::Error> {
+        let vpc_peerings = self
             .client
             .describe_vpc_peering_connections()
-            .optionally_filter(self.requester_vpc_filter())
+            .optionally_filter(filter)
             .fold_filters(self.tag_filter())
             .into_paginator()
             .items()
@@ -391,11 +1100,11 @@ impl Ec2Resources {
             .collect::<Result<_, _>>()
             .await?;
 
-        Ok(())
+        Ok(vpc_peerings)
     }
 
-    async fn collect_vpc_endpoints(&mut self) -> Result<(), ec2::Error> {
-        self.vpc_endpoints = self
+    async fn collect_vpc_endpoints(&self) -> Result<Vec<ec2::types::VpcEndpoint>, ec2::Error> {
+        let vpc_endpoints = self
             .client
             .describe_vpc_endpoints()
             .optionally_filter(self.vpc_filter())
@@ -406,11 +1115,11 @@ impl Ec2Resources {
             .collect::<Result<_, _>>()
             .await?;
 
-        Ok(())
+        Ok(vpc_endpoints)
     }
 
-    async fn collect_nat_gateways(&mut self) -> Result<(), ec2::Error> {
-        self.nat_gateways = self
+    async fn collect_nat_gateways(&self) -> Result<Vec<ec2::types::NatGateway>, ec2::Error> {
+        let nat_gateways = self
             .client
             .describe_nat_gateways()
             .optionally_filter(self.vpc_filter())
@@ -421,11 +1130,11 @@ impl Ec2Resources {
             .collect::<Result<_, _>>()
             .await?;
 
-        Ok(())
+        Ok(nat_gateways)
     }
 
-    async fn collect_security_groups(&mut self) -> Result<(), ec2::Error> {
-        self.security_groups = self
+    async fn collect_security_groups(&self) -> Result<Vec<ec2::types::SecurityGroup>, ec2::Error> {
+        let security_groups = self
             .client
             .describe_security_groups()
             .optionally_filter(self.vpc_filter())
@@ -436,25 +1145,24 @@ impl Ec2Resources {
             .collect::<Result<_, _>>()
             .await?;
 
-        Ok(())
+        Ok(security_groups)
     }
 
-    async fn collect_vpn_connections(&mut self) -> Result<(), ec2::Error> {
-        self.vpn_connections = self
+    async fn collect_vpn_connections(&self) -> Result<Vec<ec2::types::VpnConnection>, ec2::Error> {
+        let vpn_connections = self
             .client
             .describe_vpn_connections()
-            .optionally_filter(self.vpc_filter())
             .fold_filters(self.tag_filter())
             .send()
             .await?
             .vpn_connections
             .unwrap_or_default();
 
-        Ok(())
+        Ok(vpn_connections)
     }
 
-    async fn collect_vpn_gateways(&mut self) -> Result<(), ec2::Error> {
-        self.vpn_gateways = self
+    async fn collect_vpn_gateways(&self) -> Result<Vec<ec2::types::VpnGateway>, ec2::Error> {
+        let vpn_gateways = self
             .client
             .describe_vpn_gateways()
             .optionally_filter(self.attachment_vpc_filter())
@@ -464,11 +1172,13 @@ impl Ec2Resources {
             .vpn_gateways
             .unwrap_or_default();
 
-        Ok(())
+        Ok(vpn_gateways)
     }
 
-    async fn collect_network_interfaces(&mut self) -> Result<(), ec2::Error> {
-        self.network_interfaces = self
+    async fn collect_network_interfaces(
+        &self,
+    ) -> Result<Vec<ec2::types::NetworkInterface>, ec2::Error> {
+        let network_interfaces = self
             .client
             .describe_network_interfaces()
             .optionally_filter(self.vpc_filter())
@@ -479,7 +1189,217 @@ impl Ec2Resources {
             .collect::<Result<_, _>>()
             .await?;
 
-        Ok(())
+        Ok(network_interfaces)
+    }
+
+    async fn collect_customer_gateways(
+        &self,
+    ) -> Result<Vec<ec2::types::CustomerGateway>, ec2::Error> {
+        let customer_gateways = self
+            .client
+            .describe_customer_gateways()
+            .fold_filters(self.tag_filter())
+            .send()
+            .await?
+            .customer_gateways
+            .unwrap_or_default();
+
+        Ok(customer_gateways)
+    }
+
+    async fn collect_transit_gateways(
+        &self,
+    ) -> Result<Vec<ec2::types::TransitGateway>, ec2::Error> {
+        let transit_gateways = self
+            .client
+            .describe_transit_gateways()
+            .fold_filters(self.tag_filter())
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(transit_gateways)
+    }
+
+    async fn collect_elastic_ips(&self) -> Result<Vec<ec2::types::Address>, ec2::Error> {
+        let elastic_ips = self
+            .client
+            .describe_addresses()
+            .filters(filter("domain", ["vpc"]))
+            .fold_filters(self.tag_filter())
+            .send()
+            .await?
+            .addresses
+            .unwrap_or_default();
+
+        Ok(elastic_ips)
+    }
+
+    async fn collect_egress_only_internet_gateways(
+        &self,
+    ) -> Result<Vec<ec2::types::EgressOnlyInternetGateway>, ec2::Error> {
+        let egress_only_internet_gateways = self
+            .client
+            .describe_egress_only_internet_gateways()
+            .fold_filters(self.tag_filter())
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(egress_only_internet_gateways)
+    }
+
+    async fn collect_carrier_gateways(
+        &self,
+    ) -> Result<Vec<ec2::types::CarrierGateway>, ec2::Error> {
+        let carrier_gateways = self
+            .client
+            .describe_carrier_gateways()
+            .optionally_filter(self.vpc_filter())
+            .fold_filters(self.tag_filter())
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(carrier_gateways)
+    }
+
+    /// The option sets the VPCs use, whatever their tags: the default set usually has none
+    async fn collect_dhcp_options(&self) -> Result<Vec<ec2::types::DhcpOptions>, ec2::Error> {
+        let dhcp_options_ids = self
+            .vpcs
+            .iter()
+            .filter_map(|vpc| vpc.dhcp_options_id())
+            .collect::<BTreeSet<_>>();
+        if dhcp_options_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let dhcp_options = self
+            .client
+            .describe_dhcp_options()
+            .filters(filter("dhcp-options-id", dhcp_options_ids))
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(dhcp_options)
+    }
+
+    async fn collect_launch_templates(
+        &self,
+    ) -> Result<Vec<ec2::types::LaunchTemplate>, ec2::Error> {
+        let launch_templates = self
+            .client
+            .describe_launch_templates()
+            .fold_filters(self.tag_filter())
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(launch_templates)
+    }
+
+    /// Only the versions Auto Scaling groups usually follow, for every launch template at once
+    async fn collect_launch_template_versions(
+        &self,
+    ) -> Result<Vec<ec2::types::LaunchTemplateVersion>, ec2::Error> {
+        let versions = self
+            .client
+            .describe_launch_template_versions()
+            .versions("$Default")
+            .versions("$Latest")
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(versions)
+    }
+
+    /// The volumes of the instances that were kept, whatever their tags; with every VPC explored
+    /// the unattached volumes carrying the tags are added
+    async fn collect_volumes(&self) -> Result<Vec<ec2::types::Volume>, ec2::Error> {
+        if self.all_vpcs && self.tags.is_empty() {
+            return self.describe_volumes(vec![]).await;
+        }
+        let volume_ids = self
+            .instances
+            .iter()
+            .flat_map(|instance| instance.block_device_mappings().unwrap_or_default())
+            .filter_map(|mapping| mapping.ebs().and_then(|ebs| ebs.volume_id()))
+            .collect::<BTreeSet<_>>();
+        let attached = async {
+            if volume_ids.is_empty() {
+                return Ok(vec![]);
+            }
+            self.describe_volumes(vec![filter("volume-id", volume_ids)])
+                .await
+        };
+        let unattached = async {
+            if !self.all_vpcs {
+                return Ok(vec![]);
+            }
+            let mut filters = self.tag_filter();
+            filters.push(filter("status", ["available"]));
+            self.describe_volumes(filters).await
+        };
+        let (mut volumes, unattached) = tokio::try_join!(attached, unattached)?;
+        volumes.extend(unattached);
+
+        Ok(volumes)
+    }
+
+    async fn describe_volumes(
+        &self,
+        filters: Vec<ec2::types::Filter>,
+    ) -> Result<Vec<ec2::types::Volume>, ec2::Error> {
+        let volumes = self
+            .client
+            .describe_volumes()
+            .fold_filters(filters)
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(volumes)
+    }
+
+    /// Only snapshots of this account, DescribeSnapshots lists every public snapshot otherwise,
+    /// and only those of the collected volumes
+    async fn collect_snapshots(&self) -> Result<Vec<ec2::types::Snapshot>, ec2::Error> {
+        let volume_filter = if self.all_vpcs && self.tags.is_empty() {
+            None
+        } else if self.volumes.is_empty() {
+            return Ok(vec![]);
+        } else {
+            let volume_ids = self.volumes.iter().filter_map(|volume| volume.volume_id());
+            Some(filter("volume-id", volume_ids))
+        };
+        let snapshots = self
+            .client
+            .describe_snapshots()
+            .owner_ids("self")
+            .optionally_filter(volume_filter)
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(snapshots)
     }
 
     fn vpc_filter(&self) -> Option<ec2::types::Filter> {
@@ -516,6 +1436,19 @@ impl Ec2Resources {
         }
     }
 
+    fn resource_vpc_filter(&self) -> Option<ec2::types::Filter> {
+        let vpcs = self
+            .vpcs
+            .iter()
+            .filter_map(|vpc| vpc.vpc_id.clone())
+            .collect::<Vec<_>>();
+        if vpcs.is_empty() {
+            None
+        } else {
+            Some(filter("resource-id", vpcs))
+        }
+    }
+
     fn requester_vpc_filter(&self) -> Option<ec2::types::Filter> {
         let vpcs = self
             .vpcs
@@ -528,9 +1461,113 @@ impl Ec2Resources {
             Some(filter("requester-vpc-info.vpc-id", vpcs))
         }
     }
+
+    fn accepter_vpc_filter(&self) -> Option<ec2::types::Filter> {
+        let vpcs = self
+            .vpcs
+            .iter()
+            .filter_map(|vpc| vpc.vpc_id.clone())
+            .collect::<Vec<_>>();
+        if vpcs.is_empty() {
+            None
+        } else {
+            Some(filter("accepter-vpc-info.vpc-id", vpcs))
+        }
+    }
 }
 
-pub(crate) async fn get_all_regions() -> Result<Vec<String>, ec2::Error> {
+impl Render for Ec2Resources {
+    fn trees(&self) -> Vec<ptree::item::StringItem> {
+        if self.list_tags {
+            vec![self.tag_tree()]
+        } else {
+            let vpcs = self.vpcs().iter().map(|vpc| self.vpc_tree(vpc));
+            let transit_gateways = self
+                .transit_gateways
+                .iter()
+                .map(|transit_gateway| self.transit_gateway_tree(transit_gateway));
+            let unassociated = self.unassociated_elastic_ip_tree();
+            let unattached = self.unattached_volume_tree();
+            vpcs.chain(transit_gateways)
+                .chain(unassociated)
+                .chain(unattached)
+                .collect()
+        }
+    }
+
+    fn json(&self) -> serde_json::Value {
+        if self.list_tags {
+            let tags = self
+                .tag_descriptions
+                .iter()
+                .map(|tag| {
+                    serde_json::json!({
+                        "key": tag.key(),
+                        "value": tag.value(),
+                        "resource_type": tag.resource_type().map(|r| r.as_str()),
+                        "resource_id": tag.resource_id(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            serde_json::json!({ "tags": tags })
+        } else {
+            let vpcs = self
+                .vpcs()
+                .iter()
+                .map(|vpc| self.vpc_json(vpc))
+                .collect::<Vec<_>>();
+            let transit_gateways = self
+                .transit_gateways
+                .iter()
+                .map(|transit_gateway| self.transit_gateway_json(transit_gateway))
+                .collect::<Vec<_>>();
+            let unassociated = json_children(self.unassociated_elastic_ips());
+            let unattached = self
+                .unattached_volumes()
+                .into_iter()
+                .map(|volume| self.volume_json(volume, None))
+                .collect::<Vec<_>>();
+            serde_json::json!({
+                "vpcs": vpcs,
+                "transit_gateways": transit_gateways,
+                "unassociated_elastic_ips": unassociated,
+                "unattached_volumes": unattached,
+            })
+        }
+    }
+
+    fn dot(&self) -> Option<Vec<String>> {
+        if self.list_tags {
+            None
+        } else {
+            Some(
+                self.vpcs()
+                    .iter()
+                    .flat_map(|vpc| self.vpc_dot(vpc))
+                    .collect(),
+            )
+        }
+    }
+
+    fn mermaid(&self) -> Option<Vec<String>> {
+        if self.list_tags {
+            None
+        } else {
+            Some(
+                self.vpcs()
+                    .iter()
+                    .flat_map(|vpc| self.vpc_mermaid(vpc))
+                    .collect(),
+            )
+        }
+    }
+
+    fn warnings(&self) -> Vec<String> {
+        self.warnings.clone()
+    }
+}
+
+pub(crate) async fn get_all_regions(include_not_opted_in: bool) -> Result<Vec<String>, ec2::Error> {
     let shared_config = aws_config::load_from_env().await;
 
     let regions = ec2::Client::new(&shared_config)
@@ -541,6 +1578,7 @@ pub(crate) async fn get_all_regions() -> Result<Vec<String>, ec2::Error> {
         .regions
         .unwrap_or_default()
         .into_iter()
+        .filter(|region| include_not_opted_in || region.opt_in_status() != Some("not-opted-in"))
         .filter_map(|region| region.region_name)
         .collect();
 
@@ -559,11 +1597,56 @@ fn filter(
 }
 
 fn add_children(ptree: &mut ptree::TreeBuilder, title: impl ToString, resources: Vec<impl Show>) {
+    add_children_with(ptree, title, resources, |_, _| {});
+}
+
+fn add_children_with<T: Show>(
+    ptree: &mut ptree::TreeBuilder,
+    title: impl ToString,
+    resources: Vec<T>,
+    mut children: impl FnMut(&mut ptree::TreeBuilder, &T),
+) {
     if !resources.is_empty() {
         ptree.begin_child(title.to_string());
         resources.into_iter().for_each(|resource| {
-            ptree.add_empty_child(resource.id_and_name());
+            ptree.begin_child(resource.id_and_name());
+            children(ptree, &resource);
+            ptree.end_child();
         });
         ptree.end_child();
     }
 }
+
+fn json_children(resources: Vec<impl Show>) -> Vec<serde_json::Value> {
+    resources.iter().map(Show::json).collect()
+}
+
+/// The id and name of a resource followed by the given details
+fn json_with(resource: impl Show, details: serde_json::Value) -> serde_json::Value {
+    let mut json = resource.json();
+    if let (Some(json), serde_json::Value::Object(details)) = (json.as_object_mut(), details) {
+        json.extend(details);
+    }
+    json
+}
+
+fn route_target(route: &ec2::types::Route) -> Option<&str> {
+    route
+        .gateway_id()
+        .or_else(|| route.nat_gateway_id())
+        .or_else(|| route.vpc_peering_connection_id())
+        .or_else(|| route.transit_gateway_id())
+        .or_else(|| route.egress_only_internet_gateway_id())
+        .or_else(|| route.carrier_gateway_id())
+        .or_else(|| route.local_gateway_id())
+        .or_else(|| route.network_interface_id())
+        .or_else(|| route.instance_id())
+        .or_else(|| route.core_network_arn())
+}
+
+fn route_destination(route: &ec2::types::Route) -> Option<&str> {
+    route
+        .destination_cidr_block()
+        .or_else(|| route.destination_ipv6_cidr_block())
+        .or_else(|| route.destination_prefix_list_id())
+}
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use aws_sdk_autoscaling as autoscaling;
+
+use crate::aws::autoscaling::{group_subnets, group_title, launch_template, GROUP_NAME_TAG};
+
+use super::*;
+
+impl Ec2Resources {
+    /// Instances launched by Auto Scaling are grouped under their group, the others follow
+    pub(super) fn add_instances(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        instances: Vec<&ec2::types::Instance>,
+    ) {
+        if instances.is_empty() {
+            return;
+        }
+
+        let mut groups = BTreeMap::<&str, Vec<&ec2::types::Instance>>::new();
+        let mut standalone = vec![];
+        for instance in instances {
+            let group = instance
+                .tags()
+                .unwrap_or_default()
+                .iter()
+                .find(|tag| tag.key() == Some(GROUP_NAME_TAG))
+                .and_then(|tag| tag.value());
+            match group {
+                Some(group) => groups.entry(group).or_default().push(instance),
+                None => standalone.push(instance),
+            }
+        }
+
+        tree.begin_child(String::from("Instances"));
+        for (group_name, instances) in groups {
+            match self.auto_scaling.group(group_name) {
+                Some(group) => {
+                    tree.begin_child(group_title(group));
+                    if let Some(launch_source) = self.launch_source(group) {
+                        tree.add_empty_child(launch_source);
+                    }
+                }
+                None => {
+                    tree.begin_child(format!("Auto Scaling Group {group_name}"));
+                }
+            }
+            for instance in instances {
+                self.add_instance(tree, instance);
+            }
+            tree.end_child();
+        }
+        for instance in standalone {
+            self.add_instance(tree, instance);
+        }
+        tree.end_child();
+    }
+
+    fn add_instance(&self, tree: &mut ptree::TreeBuilder, instance: &ec2::types::Instance) {
+        tree.begin_child(instance.id_and_name());
+        if let Some(eks) = details::eks_membership(instance.tags().unwrap_or_default()) {
+            tree.add_empty_child(eks);
+        }
+        if self.options.details {
+            details::instance(tree, &instance);
+        }
+        self.instance_volumes(tree, &instance);
+        tree.end_child();
+    }
+
+    /// The launch template version or the launch configuration a group launches instances from
+    fn launch_source(&self, group: &autoscaling::types::AutoScalingGroup) -> Option<String> {
+        if let Some(specification) = launch_template(group) {
+            let launch_template = self.launch_templates.iter().find(|launch_template| {
+                (specification.launch_template_id().is_some()
+                    && launch_template.launch_template_id() == specification.launch_template_id())
+                    || (specification.launch_template_name().is_some()
+                        && launch_template.launch_template_name()
+                            == specification.launch_template_name())
+            });
+            let title = launch_template.map_or_else(
+                || {
+                    specification
+                        .launch_template_id()
+                        .or_else(|| specification.launch_template_name())
+                        .unwrap_or("unknown")
+                        .to_string()
+                },
+                |launch_template| launch_template.id_and_name(),
+            );
+            let requested = specification.version().unwrap_or("$Default");
+            // Groups usually follow $Default or $Latest, so resolve those to a number
+            let number = launch_template.and_then(|launch_template| match requested {
+                "$Default" => launch_template.default_version_number(),
+                "$Latest" => launch_template.latest_version_number(),
+                version => version.parse().ok(),
+            });
+            let version = match number {
+                Some(number) if requested.starts_with('$') => format!("{requested} (v{number})"),
+                _ => requested.to_string(),
+            };
+            let description = launch_template
+                .zip(number)
+                .and_then(|(launch_template, number)| {
+                    self.launch_template_version(launch_template, number)
+                })
+                .and_then(|version| version.version_description())
+                .map(|description| format!(": {description}"))
+                .unwrap_or_default();
+            return Some(format!(
+                "Launch Template: {title} version {version}{description}"
+            ));
+        }
+
+        let name = group.launch_configuration_name()?;
+        let details = self
+            .auto_scaling
+            .launch_configuration(name)
+            .map(|launch_configuration| {
+                let instance_type = launch_configuration.instance_type().unwrap_or("no type");
+                let image_id = launch_configuration.image_id().unwrap_or("no image");
+                format!(" ({instance_type}, {image_id})")
+            })
+            .unwrap_or_default();
+        Some(format!("Launch Configuration: {name}{details}"))
+    }
+
+    fn launch_template_version(
+        &self,
+        launch_template: &ec2::types::LaunchTemplate,
+        number: i64,
+    ) -> Option<&ec2::types::LaunchTemplateVersion> {
+        self.launch_template_versions.iter().find(|version| {
+            version.launch_template_id() == launch_template.launch_template_id()
+                && version.version_number() == Some(number)
+        })
+    }
+
+    /// Groups launching into the subnets of this VPC, with or without running instances
+    pub(super) fn auto_scaling_groups(
+        &self,
+        vpc_id: impl AsRef<str>,
+    ) -> Vec<&autoscaling::types::AutoScalingGroup> {
+        let subnets = self.subnets(vpc_id);
+        let subnet_ids = subnets
+            .iter()
+            .filter_map(|subnet| subnet.subnet_id())
+            .collect::<Vec<_>>();
+        self.auto_scaling.groups_in(&subnet_ids)
+    }
+
+    pub(super) fn auto_scaling_group_json(
+        &self,
+        group: &autoscaling::types::AutoScalingGroup,
+    ) -> serde_json::Value {
+        let instances = group
+            .instances()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|instance| instance.instance_id())
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "id": group.auto_scaling_group_name(),
+            "name": group.auto_scaling_group_name(),
+            "desired_capacity": group.desired_capacity(),
+            "min_size": group.min_size(),
+            "max_size": group.max_size(),
+            "launch_source": self.launch_source(group),
+            "subnets": group_subnets(group),
+            "instances": instances,
+        })
+    }
+}
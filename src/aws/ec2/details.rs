@@ -0,0 +1,586 @@
+use std::collections::BTreeMap;
+
+use super::*;
+
+pub(super) fn instance(tree: &mut ptree::TreeBuilder, instance: &&ec2::types::Instance) {
+    let state = instance
+        .state()
+        .and_then(|state| state.name())
+        .map(|name| name.as_str());
+    let zone = instance
+        .placement()
+        .and_then(|placement| placement.availability_zone());
+    add_detail(tree, "State", state);
+    add_detail(
+        tree,
+        "Type",
+        instance.instance_type().map(|r#type| r#type.as_str()),
+    );
+    add_detail(tree, "Private IP", instance.private_ip_address());
+    add_detail(tree, "Public IP", instance.public_ip_address());
+    add_detail(tree, "Availability Zone", zone);
+}
+
+pub(super) fn instance_json(instance: &ec2::types::Instance) -> serde_json::Value {
+    let state = instance
+        .state()
+        .and_then(|state| state.name())
+        .map(|name| name.as_str());
+    let zone = instance
+        .placement()
+        .and_then(|placement| placement.availability_zone());
+    json_with(
+        instance,
+        serde_json::json!({
+            "state": state,
+            "type": instance.instance_type().map(|r#type| r#type.as_str()),
+            "private_ip": instance.private_ip_address(),
+            "public_ip": instance.public_ip_address(),
+            "availability_zone": zone,
+        }),
+    )
+}
+
+/// The EKS cluster (and node group) a resource belongs to, from the tags EKS, eksctl and
+/// Kubernetes put on what they create
+pub(super) fn eks_membership(tags: &[ec2::types::Tag]) -> Option<String> {
+    let tag = |key: &str| {
+        tags.iter()
+            .find(|tag| tag.key() == Some(key))
+            .and_then(|tag| tag.value())
+    };
+    let cluster = tag("eks:cluster-name")
+        .or_else(|| tag("aws:eks:cluster-name"))
+        .or_else(|| {
+            tags.iter()
+                .filter_map(|tag| tag.key())
+                .find_map(|key| key.strip_prefix("kubernetes.io/cluster/"))
+        })?;
+    match tag("eks:nodegroup-name") {
+        Some(nodegroup) => Some(format!("EKS: {cluster}, node group {nodegroup}")),
+        None => Some(format!("EKS: {cluster}")),
+    }
+}
+
+pub(super) fn elastic_ip(tree: &mut ptree::TreeBuilder, address: &&ec2::types::Address) {
+    add_detail(tree, "Public IP", address.public_ip());
+    add_detail(tree, "Private IP", address.private_ip_address());
+    let associated = address
+        .instance_id()
+        .filter(|instance_id| !instance_id.is_empty())
+        .or_else(|| address.network_interface_id());
+    add_detail(tree, "Associated with", associated);
+}
+
+pub(super) fn dhcp_options(tree: &mut ptree::TreeBuilder, dhcp_options: &&ec2::types::DhcpOptions) {
+    for configuration in dhcp_options.dhcp_configurations().unwrap_or_default() {
+        let values = configuration
+            .values()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|value| value.value())
+            .collect::<Vec<_>>();
+        let key = configuration.key().unwrap_or("unknown");
+        tree.add_empty_child(format!("{key}: {}", values.join(", ")));
+    }
+}
+
+/// Status of the peering and the VPC on the other side of it
+pub(super) fn vpc_peering(
+    tree: &mut ptree::TreeBuilder,
+    vpc_peering: &&ec2::types::VpcPeeringConnection,
+    vpc_id: &str,
+) {
+    add_detail(tree, "Status", peering_status(vpc_peering));
+
+    let (role, remote) = peering_remote(vpc_peering, vpc_id);
+    if let Some(remote) = remote {
+        tree.begin_child(format!(
+            "{role}: {}",
+            remote.vpc_id().unwrap_or("unknown VPC")
+        ));
+        let cidrs = peering_cidrs(remote);
+        if !cidrs.is_empty() {
+            tree.add_empty_child(format!("CIDRs: {}", cidrs.join(", ")));
+        }
+        add_detail(tree, "Owner", remote.owner_id());
+        add_detail(tree, "Region", remote.region());
+        tree.end_child();
+    }
+}
+
+pub(super) fn vpc_peering_json(
+    vpc_peering: &ec2::types::VpcPeeringConnection,
+    vpc_id: &str,
+) -> serde_json::Value {
+    let (role, remote) = peering_remote(vpc_peering, vpc_id);
+    let remote = remote.map(|remote| {
+        serde_json::json!({
+            "role": role.to_lowercase(),
+            "vpc_id": remote.vpc_id(),
+            "cidrs": peering_cidrs(remote),
+            "owner": remote.owner_id(),
+            "region": remote.region(),
+        })
+    });
+    json_with(
+        vpc_peering,
+        serde_json::json!({
+            "status": peering_status(vpc_peering),
+            "remote": remote,
+        }),
+    )
+}
+
+fn peering_status(vpc_peering: &ec2::types::VpcPeeringConnection) -> Option<&str> {
+    vpc_peering
+        .status()
+        .and_then(|status| status.code())
+        .map(|code| code.as_str())
+}
+
+/// The role of the VPC on the other side of the peering, and that VPC
+fn peering_remote<'a>(
+    vpc_peering: &'a ec2::types::VpcPeeringConnection,
+    vpc_id: &str,
+) -> (
+    &'static str,
+    Option<&'a ec2::types::VpcPeeringConnectionVpcInfo>,
+) {
+    let requester = vpc_peering.requester_vpc_info();
+    let accepter = vpc_peering.accepter_vpc_info();
+    if requester.and_then(|info| info.vpc_id()) == Some(vpc_id) {
+        ("Accepter", accepter)
+    } else {
+        ("Requester", requester)
+    }
+}
+
+fn peering_cidrs(remote: &ec2::types::VpcPeeringConnectionVpcInfo) -> Vec<&str> {
+    remote
+        .cidr_block_set()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|cidr| cidr.cidr_block())
+        .chain(
+            remote
+                .ipv6_cidr_block_set()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|cidr| cidr.ipv6_cidr_block()),
+        )
+        .collect()
+}
+
+impl Ec2Resources {
+    pub(super) fn route_table_details(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        route_table: &&ec2::types::RouteTable,
+    ) {
+        let associations = route_table.associations().unwrap_or_default();
+
+        if associations
+            .iter()
+            .any(|association| association.main().unwrap_or_default())
+        {
+            tree.add_empty_child(String::from("Main route table"));
+        }
+
+        let routes = route_table.routes().unwrap_or_default();
+        if !routes.is_empty() {
+            tree.begin_child(String::from("Routes"));
+            for route in routes {
+                tree.add_empty_child(route_title(route));
+            }
+            tree.end_child();
+        }
+
+        let associated = associations
+            .iter()
+            .filter_map(|association| {
+                association
+                    .subnet_id()
+                    .map(|subnet_id| self.subnet_title(subnet_id))
+                    .or_else(|| association.gateway_id().map(ToString::to_string))
+            })
+            .collect::<Vec<_>>();
+        if !associated.is_empty() {
+            tree.begin_child(String::from("Associations"));
+            for title in associated {
+                tree.add_empty_child(title);
+            }
+            tree.end_child();
+        }
+    }
+
+    pub(super) fn route_table_json(
+        &self,
+        route_table: &ec2::types::RouteTable,
+    ) -> serde_json::Value {
+        let associations = route_table.associations().unwrap_or_default();
+        let routes = route_table
+            .routes()
+            .unwrap_or_default()
+            .iter()
+            .map(|route| {
+                serde_json::json!({
+                    "destination": route_destination(route),
+                    "target": route_target(route),
+                    "state": route.state().map(|state| state.as_str()),
+                })
+            })
+            .collect::<Vec<_>>();
+        let associated = associations
+            .iter()
+            .filter_map(|association| association.subnet_id().or_else(|| association.gateway_id()))
+            .collect::<Vec<_>>();
+        json_with(
+            route_table,
+            serde_json::json!({
+                "main": associations
+                    .iter()
+                    .any(|association| association.main().unwrap_or_default()),
+                "routes": routes,
+                "associations": associated,
+            }),
+        )
+    }
+
+    pub(super) fn instance_volumes(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        instance: &&ec2::types::Instance,
+    ) {
+        let instance_id = instance.instance_id().unwrap_or_default();
+        let volumes = self.instance_volumes_of(instance_id);
+        if !volumes.is_empty() {
+            tree.begin_child(String::from("Volumes"));
+            for volume in volumes {
+                let attachment = volume
+                    .attachments()
+                    .unwrap_or_default()
+                    .iter()
+                    .find(|attachment| attachment.instance_id() == Some(instance_id));
+                self.add_volume(tree, volume, attachment);
+            }
+            tree.end_child();
+        }
+    }
+
+    /// A volume with the snapshots taken of it
+    fn add_volume(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        volume: &ec2::types::Volume,
+        attachment: Option<&ec2::types::VolumeAttachment>,
+    ) {
+        tree.begin_child(volume_title(volume, attachment));
+        for snapshot in self.volume_snapshots_of(volume.volume_id()) {
+            tree.add_empty_child(snapshot_title(snapshot));
+        }
+        tree.end_child();
+    }
+
+    pub(super) fn volume_json(
+        &self,
+        volume: &ec2::types::Volume,
+        attachment: Option<&ec2::types::VolumeAttachment>,
+    ) -> serde_json::Value {
+        let snapshots = self
+            .volume_snapshots_of(volume.volume_id())
+            .into_iter()
+            .map(|snapshot| {
+                json_with(
+                    snapshot,
+                    serde_json::json!({
+                        "size": snapshot.volume_size(),
+                        "started": snapshot_started(snapshot),
+                        "state": snapshot.state().map(|state| state.as_str()),
+                    }),
+                )
+            })
+            .collect::<Vec<_>>();
+        let device = attachment.and_then(|attachment| attachment.device());
+        let delete_on_termination =
+            attachment.and_then(|attachment| attachment.delete_on_termination());
+        json_with(
+            volume,
+            serde_json::json!({
+                "size": volume.size(),
+                "type": volume.volume_type().map(|r#type| r#type.as_str()),
+                "encrypted": volume.encrypted(),
+                "availability_zone": volume.availability_zone(),
+                "device": device,
+                "delete_on_termination": delete_on_termination,
+                "snapshots": snapshots,
+            }),
+        )
+    }
+
+    pub(super) fn unassociated_elastic_ip_tree(&self) -> Option<ptree::item::StringItem> {
+        let addresses = self.unassociated_elastic_ips();
+        if addresses.is_empty() {
+            return None;
+        }
+
+        let mut tree = ptree::TreeBuilder::new(String::from("Unassociated Elastic IPs"));
+        for address in addresses {
+            tree.begin_child(address.id_and_name());
+            elastic_ip(&mut tree, &address);
+            tree.end_child();
+        }
+        Some(tree.build())
+    }
+
+    pub(super) fn unattached_volume_tree(&self) -> Option<ptree::item::StringItem> {
+        let volumes = self.unattached_volumes();
+        if volumes.is_empty() {
+            return None;
+        }
+
+        let mut zones = BTreeMap::<&str, Vec<&ec2::types::Volume>>::new();
+        for volume in volumes {
+            let zone = volume.availability_zone().unwrap_or("unknown zone");
+            zones.entry(zone).or_default().push(volume);
+        }
+
+        let mut tree = ptree::TreeBuilder::new(String::from("Unattached Volumes"));
+        for (zone, volumes) in zones {
+            tree.begin_child(zone.to_string());
+            for volume in volumes {
+                self.add_volume(&mut tree, volume, None);
+            }
+            tree.end_child();
+        }
+        Some(tree.build())
+    }
+
+    pub(super) fn security_group_rules(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        security_group: &&ec2::types::SecurityGroup,
+    ) {
+        let ingress = security_group.ip_permissions().unwrap_or_default();
+        let egress = security_group.ip_permissions_egress().unwrap_or_default();
+        self.add_permissions(tree, "Ingress", ingress);
+        self.add_permissions(tree, "Egress", egress);
+    }
+
+    pub(super) fn security_group_json(
+        &self,
+        security_group: &ec2::types::SecurityGroup,
+    ) -> serde_json::Value {
+        let permissions = |permissions: Option<&[ec2::types::IpPermission]>| {
+            permissions
+                .unwrap_or_default()
+                .iter()
+                .map(|permission| {
+                    serde_json::json!({
+                        "rule": permission_title(permission),
+                        "sources": self.permission_sources(permission),
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+        json_with(
+            security_group,
+            serde_json::json!({
+                "group_name": security_group.group_name(),
+                "ingress": permissions(security_group.ip_permissions()),
+                "egress": permissions(security_group.ip_permissions_egress()),
+            }),
+        )
+    }
+
+    fn add_permissions(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        title: &str,
+        permissions: &[ec2::types::IpPermission],
+    ) {
+        if permissions.is_empty() {
+            return;
+        }
+
+        tree.begin_child(title.to_string());
+        for permission in permissions {
+            tree.begin_child(permission_title(permission));
+            for source in self.permission_sources(permission) {
+                tree.add_empty_child(source);
+            }
+            tree.end_child();
+        }
+        tree.end_child();
+    }
+
+    fn permission_sources(&self, permission: &ec2::types::IpPermission) -> Vec<String> {
+        let ipv4 = permission
+            .ip_ranges()
+            .unwrap_or_default()
+            .iter()
+            .map(|range| with_description(range.cidr_ip(), range.description()));
+        let ipv6 = permission
+            .ipv6_ranges()
+            .unwrap_or_default()
+            .iter()
+            .map(|range| with_description(range.cidr_ipv6(), range.description()));
+        let prefix_lists = permission
+            .prefix_list_ids()
+            .unwrap_or_default()
+            .iter()
+            .map(|list| with_description(list.prefix_list_id(), list.description()));
+        let groups = permission
+            .user_id_group_pairs()
+            .unwrap_or_default()
+            .iter()
+            .map(|pair| {
+                let group = pair
+                    .group_id()
+                    .map(|group_id| self.security_group_title(group_id));
+                with_description(group.as_deref(), pair.description())
+            });
+        ipv4.chain(ipv6).chain(prefix_lists).chain(groups).collect()
+    }
+
+    fn security_group_title(&self, group_id: &str) -> String {
+        self.security_groups
+            .iter()
+            .find(|security_group| security_group.group_id() == Some(group_id))
+            .and_then(|security_group| security_group.group_name())
+            .map_or_else(
+                || group_id.to_string(),
+                |name| format!("{group_id} ({name})"),
+            )
+    }
+
+    pub(super) fn subnet_title(&self, subnet_id: &str) -> String {
+        self.subnets
+            .iter()
+            .find(|subnet| subnet.subnet_id() == Some(subnet_id))
+            .map_or_else(|| subnet_id.to_string(), |subnet| subnet.id_and_name())
+    }
+}
+
+fn route_title(route: &ec2::types::Route) -> String {
+    let destination = route_destination(route).unwrap_or("no destination");
+    let target = route_target(route).unwrap_or("no target");
+    if route.state() == Some(&ec2::types::RouteState::Blackhole) {
+        format!("{destination} → {target} [BLACKHOLE]")
+    } else {
+        format!("{destination} → {target}")
+    }
+}
+
+fn permission_title(permission: &ec2::types::IpPermission) -> String {
+    let protocol = match permission.ip_protocol() {
+        Some("-1") | None => return String::from("All traffic"),
+        Some(protocol) => protocol,
+    };
+    // ICMP rules carry the ICMP type and code in the port fields, -1 meaning all of them
+    if matches!(protocol, "icmp" | "icmpv6" | "1" | "58") {
+        let all_or = |value: Option<i32>| match value {
+            Some(value) if value >= 0 => value.to_string(),
+            _ => String::from("all"),
+        };
+        return match permission.from_port() {
+            Some(r#type) if r#type >= 0 => {
+                format!(
+                    "{protocol} type {type}, code {}",
+                    all_or(permission.to_port())
+                )
+            }
+            _ => format!("{protocol} all"),
+        };
+    }
+    match (permission.from_port(), permission.to_port()) {
+        (Some(from), Some(to)) if from == to => format!("{protocol} {from}"),
+        (Some(from), Some(to)) if from >= 0 => format!("{protocol} {from}-{to}"),
+        _ => protocol.to_string(),
+    }
+}
+
+fn with_description(source: Option<&str>, description: Option<&str>) -> String {
+    let source = source.unwrap_or("unknown");
+    match description {
+        Some(description) if !description.is_empty() => format!("{source} ({description})"),
+        _ => source.to_string(),
+    }
+}
+
+/// One line per volume: size, type and encryption, plus device and cleanup behaviour when attached
+fn volume_title(
+    volume: &ec2::types::Volume,
+    attachment: Option<&ec2::types::VolumeAttachment>,
+) -> String {
+    let size = volume.size().unwrap_or_default();
+    let r#type = volume
+        .volume_type()
+        .map_or("unknown", |r#type| r#type.as_str());
+    let encrypted = if volume.encrypted().unwrap_or_default() {
+        "encrypted"
+    } else {
+        "not encrypted"
+    };
+    let mut title = format!("{} {size} GiB {type}, {encrypted}", volume.id_and_name());
+    if let Some(attachment) = attachment {
+        let device = attachment.device().unwrap_or("no device");
+        title.push_str(&format!(" on {device}"));
+        if attachment.delete_on_termination().unwrap_or_default() {
+            title.push_str(", deleted on termination");
+        }
+    }
+    title
+}
+
+fn snapshot_title(snapshot: &ec2::types::Snapshot) -> String {
+    let size = snapshot.volume_size().unwrap_or_default();
+    let started = snapshot_started(snapshot).unwrap_or_else(|| String::from("no date"));
+    let state = snapshot.state().map_or("unknown", |state| state.as_str());
+    format!(
+        "Snapshot {} {size} GiB, {started} [{state}]",
+        snapshot.id_and_name()
+    )
+}
+
+fn snapshot_started(snapshot: &ec2::types::Snapshot) -> Option<String> {
+    snapshot
+        .start_time()
+        .and_then(|started| started.fmt(ec2::primitives::DateTimeFormat::DateTime).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permission(protocol: &str, from: i32, to: i32) -> ec2::types::IpPermission {
+        ec2::types::IpPermission::builder()
+            .ip_protocol(protocol)
+            .from_port(from)
+            .to_port(to)
+            .build()
+    }
+
+    #[test]
+    fn permission_title_ports() {
+        assert_eq!(permission_title(&permission("tcp", 22, 22)), "tcp 22");
+        assert_eq!(
+            permission_title(&permission("tcp", 0, 65535)),
+            "tcp 0-65535"
+        );
+        assert_eq!(permission_title(&permission("-1", -1, -1)), "All traffic");
+    }
+
+    #[test]
+    fn permission_title_icmp() {
+        assert_eq!(permission_title(&permission("icmp", -1, -1)), "icmp all");
+        assert_eq!(
+            permission_title(&permission("icmp", 8, -1)),
+            "icmp type 8, code all"
+        );
+        assert_eq!(
+            permission_title(&permission("icmpv6", 3, 4)),
+            "icmpv6 type 3, code 4"
+        );
+    }
+}
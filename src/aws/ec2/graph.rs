@@ -0,0 +1,355 @@
+use std::collections::BTreeMap;
+
+use crate::{dot, mermaid};
+
+use super::*;
+
+#[derive(Debug)]
+struct Node {
+    id: String,
+    label: String,
+    shape: &'static str,
+    zone: Option<String>,
+}
+
+#[derive(Debug)]
+struct Edge {
+    from: String,
+    to: String,
+    label: Option<String>,
+}
+
+impl Node {
+    fn new(resource: impl Show, shape: &'static str, zone: Option<&str>) -> Self {
+        Self {
+            id: resource.id(),
+            label: resource.id_and_name(),
+            shape,
+            zone: zone.map(ToString::to_string),
+        }
+    }
+}
+
+impl Edge {
+    fn new(from: impl ToString, to: impl ToString, label: Option<&str>) -> Self {
+        Self {
+            from: from.to_string(),
+            to: to.to_string(),
+            label: label.map(ToString::to_string),
+        }
+    }
+}
+
+impl Ec2Resources {
+    pub(super) fn vpc_dot(&self, vpc: &ec2::types::Vpc) -> Vec<String> {
+        let (nodes, edges) = self.vpc_graph(vpc);
+        let nodes = nodes
+            .iter()
+            .map(|node| dot::node(&node.id, &node.label, node.shape))
+            .collect();
+        let mut statements = dot::cluster(vpc.id(), vpc.id_and_name(), nodes);
+        statements.extend(
+            edges
+                .iter()
+                .map(|edge| dot::edge(&edge.from, &edge.to, edge.label.as_deref())),
+        );
+        statements
+    }
+
+    pub(super) fn vpc_mermaid(&self, vpc: &ec2::types::Vpc) -> Vec<String> {
+        let (nodes, edges) = self.vpc_graph(vpc);
+        let mut zones = BTreeMap::<&str, Vec<String>>::new();
+        let mut statements = vec![];
+        for node in &nodes {
+            let statement = mermaid::node(&node.id, &node.label);
+            match node.zone.as_deref() {
+                Some(zone) => zones.entry(zone).or_default().push(statement),
+                None => statements.push(statement),
+            }
+        }
+        for (zone, nodes) in zones {
+            let id = format!("{}_{zone}", vpc.id());
+            statements.extend(mermaid::subgraph(id, zone, nodes));
+        }
+        // The VPC is a node of its own, so the subgraph around it needs a different id
+        let id = format!("{}_group", vpc.id());
+        let mut statements = mermaid::subgraph(id, vpc.id_and_name(), statements);
+        statements.extend(
+            edges
+                .iter()
+                .map(|edge| mermaid::edge(&edge.from, &edge.to, edge.label.as_deref())),
+        );
+        statements
+    }
+
+    fn vpc_graph(&self, vpc: &ec2::types::Vpc) -> (Vec<Node>, Vec<Edge>) {
+        let vpc_id = vpc.id();
+        let mut nodes = vec![Node::new(vpc, "box3d", None)];
+        let mut edges = vec![];
+
+        let subnets = self.subnets(&vpc_id);
+        let subnet_zone = |subnet_id: Option<&str>| {
+            subnets
+                .iter()
+                .find(|subnet| subnet.subnet_id() == subnet_id)
+                .and_then(|subnet| subnet.availability_zone())
+        };
+
+        for &subnet in &subnets {
+            nodes.push(Node::new(subnet, "box", subnet.availability_zone()));
+        }
+
+        for instance in self.instances(&vpc_id) {
+            let zone = instance
+                .placement()
+                .and_then(|placement| placement.availability_zone());
+            nodes.push(Node::new(instance, "component", zone));
+            if let Some(subnet_id) = instance.subnet_id() {
+                edges.push(Edge::new(instance.id(), subnet_id, None));
+            }
+        }
+
+        for igw in self.internet_gateways(&vpc_id) {
+            nodes.push(Node::new(igw, "doublecircle", None));
+            edges.push(Edge::new(igw.id(), &vpc_id, Some("attached")));
+        }
+
+        for eigw in self.egress_only_internet_gateways(&vpc_id) {
+            nodes.push(Node::new(eigw, "doublecircle", None));
+            edges.push(Edge::new(eigw.id(), &vpc_id, Some("attached")));
+        }
+
+        for carrier_gateway in self.carrier_gateways(&vpc_id) {
+            nodes.push(Node::new(carrier_gateway, "doublecircle", None));
+            edges.push(Edge::new(carrier_gateway.id(), &vpc_id, Some("attached")));
+        }
+
+        for dhcp_options in self.dhcp_options(&vpc_id) {
+            nodes.push(Node::new(dhcp_options, "note", None));
+            edges.push(Edge::new(&vpc_id, dhcp_options.id(), None));
+        }
+
+        for route_table in self.route_tables(&vpc_id) {
+            let rtb_id = route_table.id();
+            nodes.push(Node::new(route_table, "note", None));
+            for association in route_table.associations().unwrap_or_default() {
+                if let Some(subnet_id) = association.subnet_id() {
+                    edges.push(Edge::new(subnet_id, &rtb_id, Some("associated")));
+                } else if association.main().unwrap_or_default() {
+                    edges.push(Edge::new(&vpc_id, &rtb_id, Some("main")));
+                }
+            }
+            for route in route_table.routes().unwrap_or_default() {
+                if let Some(target) = route_target(route).filter(|target| *target != "local") {
+                    edges.push(Edge::new(&rtb_id, target, route_destination(route)));
+                }
+            }
+        }
+
+        for nacl in self.network_acls(&vpc_id) {
+            nodes.push(Node::new(nacl, "octagon", None));
+            for association in nacl.associations().unwrap_or_default() {
+                if let Some(subnet_id) = association.subnet_id() {
+                    edges.push(Edge::new(nacl.id(), subnet_id, None));
+                }
+            }
+        }
+
+        for peering in self.vpc_peerings(&vpc_id) {
+            nodes.push(Node::new(peering, "cds", None));
+            if let Some(requester) = peering.requester_vpc_info().and_then(|info| info.vpc_id()) {
+                edges.push(Edge::new(requester, peering.id(), Some("requester")));
+            }
+            if let Some(accepter) = peering.accepter_vpc_info().and_then(|info| info.vpc_id()) {
+                edges.push(Edge::new(peering.id(), accepter, Some("accepter")));
+            }
+        }
+
+        for endpoint in self.vpc_endpoints(&vpc_id) {
+            nodes.push(Node::new(endpoint, "cds", None));
+            for subnet_id in endpoint.subnet_ids().unwrap_or_default() {
+                edges.push(Edge::new(endpoint.id(), subnet_id, None));
+            }
+            for rtb_id in endpoint.route_table_ids().unwrap_or_default() {
+                edges.push(Edge::new(rtb_id, endpoint.id(), None));
+            }
+        }
+
+        for nat_gateway in self.nat_gateways(&vpc_id) {
+            let zone = subnet_zone(nat_gateway.subnet_id());
+            nodes.push(Node::new(nat_gateway, "circle", zone));
+            if let Some(subnet_id) = nat_gateway.subnet_id() {
+                edges.push(Edge::new(nat_gateway.id(), subnet_id, None));
+            }
+        }
+
+        for security_group in self.security_groups(&vpc_id) {
+            nodes.push(Node::new(security_group, "hexagon", None));
+        }
+
+        for vpn_connection in self.vpn_connections(&vpc_id) {
+            nodes.push(Node::new(vpn_connection, "cds", None));
+            if let Some(customer_gateway) = self.customer_gateway(vpn_connection) {
+                nodes.push(Node::new(customer_gateway, "house", None));
+                edges.push(Edge::new(customer_gateway.id(), vpn_connection.id(), None));
+            }
+            if let Some(gateway_id) = vpn_connection
+                .vpn_gateway_id()
+                .or_else(|| vpn_connection.transit_gateway_id())
+            {
+                edges.push(Edge::new(vpn_connection.id(), gateway_id, None));
+            }
+        }
+
+        for vpn_gateway in self.vpn_gateways(&vpc_id) {
+            nodes.push(Node::new(vpn_gateway, "doublecircle", None));
+            for attachment in vpn_gateway.vpc_attachments().unwrap_or_default() {
+                if let Some(attached_vpc_id) = attachment.vpc_id() {
+                    edges.push(Edge::new(
+                        vpn_gateway.id(),
+                        attached_vpc_id,
+                        Some("attached"),
+                    ));
+                }
+            }
+        }
+
+        for eni in self.network_interfaces(&vpc_id) {
+            let eni_id = eni.id();
+            nodes.push(Node::new(eni, "ellipse", eni.availability_zone()));
+            if let Some(subnet_id) = eni.subnet_id() {
+                edges.push(Edge::new(&eni_id, subnet_id, None));
+            }
+            if let Some(instance_id) = eni.attachment().and_then(|a| a.instance_id()) {
+                edges.push(Edge::new(instance_id, &eni_id, None));
+            }
+            for group_id in eni
+                .groups()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|group| group.group_id())
+            {
+                edges.push(Edge::new(&eni_id, group_id, None));
+            }
+        }
+
+        for attachment in self.transit_gateway_attachments(&vpc_id) {
+            if let Some(transit_gateway) = attachment
+                .transit_gateway_id()
+                .and_then(|transit_gateway_id| self.transit_gateway(transit_gateway_id))
+            {
+                nodes.push(Node::new(transit_gateway, "Mdiamond", None));
+                edges.push(Edge::new(&vpc_id, transit_gateway.id(), Some("attached")));
+            }
+        }
+
+        for address in self.elastic_ips(&vpc_id) {
+            nodes.push(Node::new(address, "circle", None));
+            if let Some(target) = address
+                .network_interface_id()
+                .or_else(|| address.instance_id())
+            {
+                edges.push(Edge::new(address.id(), target, None));
+            }
+        }
+
+        for database in self.databases.instances_of(&vpc_id) {
+            nodes.push(Node::new(
+                database,
+                "cylinder",
+                database.availability_zone(),
+            ));
+            if let Some(subnet_id) = self.database_subnet(database) {
+                edges.push(Edge::new(database.id(), subnet_id, None));
+            }
+        }
+
+        for (load_balancer, subnet_ids) in self.load_balancers.vpc_subnets(&vpc_id) {
+            for subnet_id in subnet_ids {
+                edges.push(Edge::new(&load_balancer, subnet_id, None));
+            }
+            nodes.push(Node {
+                id: load_balancer.clone(),
+                label: load_balancer,
+                shape: "invtrapezium",
+                zone: None,
+            });
+        }
+
+        (nodes, edges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resources() -> Ec2Resources {
+        let config = aws_types::SdkConfig::builder()
+            .region(aws_types::region::Region::new("us-west-1"))
+            .build();
+        let options = Options {
+            details: false,
+            rules: false,
+            layout: Layout::Type,
+        };
+        Ec2Resources::new(&config, &[], options)
+    }
+
+    #[test]
+    fn vpc_mermaid() {
+        let mut resources = resources();
+        let vpc = ec2::types::Vpc::builder().vpc_id("vpc-1").build();
+        resources.subnets = vec![ec2::types::Subnet::builder()
+            .subnet_id("subnet-1")
+            .vpc_id("vpc-1")
+            .availability_zone("us-west-1a")
+            .build()];
+
+        let statements = resources.vpc_mermaid(&vpc);
+
+        assert_eq!(
+            statements,
+            [
+                "subgraph vpc_1_group [\"vpc-1\"]",
+                "    vpc_1[\"vpc-1\"]",
+                "    subgraph vpc_1_us_west_1a [\"us-west-1a\"]",
+                "        subnet_1[\"subnet-1\"]",
+                "    end",
+                "end",
+            ]
+        );
+    }
+
+    #[test]
+    fn vpc_dot() {
+        let mut resources = resources();
+        let name = ec2::types::Tag::builder()
+            .key("Name")
+            .value("main \"prod\"\nwest")
+            .build();
+        let vpc = ec2::types::Vpc::builder()
+            .vpc_id("vpc-1")
+            .tags(name)
+            .build();
+        resources.instances = vec![ec2::types::Instance::builder()
+            .instance_id("i-1")
+            .vpc_id("vpc-1")
+            .subnet_id("subnet-1")
+            .build()];
+
+        let statements = resources.vpc_dot(&vpc);
+
+        assert_eq!(
+            statements,
+            [
+                "subgraph \"cluster_vpc-1\" {",
+                "    label=\"vpc-1 (main \\\"prod\\\"\\nwest)\";",
+                "    \"vpc-1\" [label=\"vpc-1 (main \\\"prod\\\"\\nwest)\", shape=box3d];",
+                "    \"i-1\" [label=\"i-1\", shape=component];",
+                "}",
+                "\"i-1\" -> \"subnet-1\";",
+            ]
+        );
+    }
+}
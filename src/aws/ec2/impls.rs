@@ -27,6 +27,9 @@ macro_rules! impl_optionally {
 }
 
 impl_optionally!(operation::describe_vpcs::builders::DescribeVpcsFluentBuilder);
+impl_optionally!(
+    operation::describe_launch_templates::builders::DescribeLaunchTemplatesFluentBuilder
+);
 impl_optionally!(operation::describe_subnets::builders::DescribeSubnetsFluentBuilder);
 impl_optionally!(operation::describe_instances::builders::DescribeInstancesFluentBuilder);
 impl_optionally!(
@@ -46,6 +49,21 @@ impl_optionally!(operation::describe_vpn_gateways::builders::DescribeVpnGateways
 impl_optionally!(
     operation::describe_network_interfaces::builders::DescribeNetworkInterfacesFluentBuilder
 );
+impl_optionally!(
+    operation::describe_customer_gateways::builders::DescribeCustomerGatewaysFluentBuilder
+);
+impl_optionally!(
+    operation::describe_transit_gateways::builders::DescribeTransitGatewaysFluentBuilder
+);
+impl_optionally!(operation::describe_transit_gateway_attachments::builders::DescribeTransitGatewayAttachmentsFluentBuilder);
+impl_optionally!(operation::describe_addresses::builders::DescribeAddressesFluentBuilder);
+impl_optionally!(operation::describe_egress_only_internet_gateways::builders::DescribeEgressOnlyInternetGatewaysFluentBuilder);
+impl_optionally!(
+    operation::describe_carrier_gateways::builders::DescribeCarrierGatewaysFluentBuilder
+);
+impl_optionally!(operation::describe_dhcp_options::builders::DescribeDhcpOptionsFluentBuilder);
+impl_optionally!(operation::describe_volumes::builders::DescribeVolumesFluentBuilder);
+impl_optionally!(operation::describe_snapshots::builders::DescribeSnapshotsFluentBuilder);
 
 // impl_optionally!(DescribeNatGateways);
 
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use super::*;
+
+/// The resources placed in one subnet, or in subnets that were not collected
+#[derive(Debug)]
+struct SubnetResources<'a> {
+    functions: Vec<&'a aws_sdk_lambda::types::FunctionConfiguration>,
+    instances: Vec<&'a ec2::types::Instance>,
+    network_interfaces: Vec<&'a ec2::types::NetworkInterface>,
+    databases: Vec<&'a aws_sdk_rds::types::DbInstance>,
+    nat_gateways: Vec<&'a ec2::types::NatGateway>,
+    vpc_endpoints: Vec<&'a ec2::types::VpcEndpoint>,
+}
+
+impl SubnetResources<'_> {
+    fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+            && self.instances.is_empty()
+            && self.network_interfaces.is_empty()
+            && self.databases.is_empty()
+            && self.nat_gateways.is_empty()
+            && self.vpc_endpoints.is_empty()
+    }
+}
+
+impl Ec2Resources {
+    /// Availability zone → subnet → the resources that live in that subnet
+    pub(super) fn add_zones(&self, tree: &mut ptree::TreeBuilder, vpc_id: &str) {
+        let subnets = self.subnets(vpc_id);
+        let mut zones = BTreeMap::<&str, Vec<&ec2::types::Subnet>>::new();
+        for &subnet in &subnets {
+            let zone = subnet.availability_zone().unwrap_or("unknown zone");
+            zones.entry(zone).or_default().push(subnet);
+        }
+
+        for (zone, subnets) in zones {
+            tree.begin_child(zone.to_string());
+            for subnet in subnets {
+                let subnet_id = subnet.subnet_id();
+                tree.begin_child(subnet.id_and_name());
+                let resources = self.subnet_resources(vpc_id, |id| id == subnet_id);
+                self.add_subnet_resources(tree, resources);
+                tree.end_child();
+            }
+            tree.end_child();
+        }
+
+        // Resources can sit in subnets left out by the filters, which must not hide them
+        let collected = subnets
+            .iter()
+            .map(|subnet| subnet.subnet_id())
+            .collect::<Vec<_>>();
+        let unknown = self.subnet_resources(vpc_id, |id| !collected.contains(&id));
+        if !unknown.is_empty() {
+            tree.begin_child(String::from("Unknown zone"));
+            self.add_subnet_resources(tree, unknown);
+            tree.end_child();
+        }
+    }
+
+    /// The resources of this VPC in the subnets accepted by `in_subnet`
+    fn subnet_resources(
+        &self,
+        vpc_id: &str,
+        in_subnet: impl Fn(Option<&str>) -> bool,
+    ) -> SubnetResources<'_> {
+        let functions = self
+            .functions
+            .functions_of(vpc_id)
+            .into_iter()
+            .filter(|function| {
+                function
+                    .vpc_config()
+                    .and_then(|config| config.subnet_ids())
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|id| in_subnet(Some(id)))
+            })
+            .collect();
+        let instances = self
+            .instances(vpc_id)
+            .into_iter()
+            .filter(|instance| in_subnet(instance.subnet_id()))
+            .collect();
+        let network_interfaces = self
+            .network_interfaces(vpc_id)
+            .into_iter()
+            .filter(|eni| in_subnet(eni.subnet_id()))
+            .collect();
+        // Databases without a known subnet are shown with the VPC itself
+        let databases = self
+            .databases
+            .instances_of(vpc_id)
+            .into_iter()
+            .filter(|database| {
+                let subnet_id = self.database_subnet(database);
+                subnet_id.is_some() && in_subnet(subnet_id)
+            })
+            .collect();
+        let nat_gateways = self
+            .nat_gateways(vpc_id)
+            .into_iter()
+            .filter(|nat_gateway| in_subnet(nat_gateway.subnet_id()))
+            .collect();
+        let vpc_endpoints = self
+            .vpc_endpoints(vpc_id)
+            .into_iter()
+            .filter(|endpoint| {
+                endpoint
+                    .subnet_ids()
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|id| in_subnet(Some(id)))
+            })
+            .collect();
+        SubnetResources {
+            functions,
+            instances,
+            network_interfaces,
+            databases,
+            nat_gateways,
+            vpc_endpoints,
+        }
+    }
+
+    fn add_subnet_resources(&self, tree: &mut ptree::TreeBuilder, resources: SubnetResources<'_>) {
+        self.add_functions(tree, resources.functions);
+        self.add_instances(tree, resources.instances);
+        self.add_network_interfaces(tree, resources.network_interfaces);
+        self.add_databases(tree, resources.databases);
+        add_children(tree, "NAT Gateways", resources.nat_gateways);
+        add_children(tree, "VPC Endpoints", resources.vpc_endpoints);
+    }
+}
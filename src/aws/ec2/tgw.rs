@@ -0,0 +1,377 @@
+use super::*;
+
+impl Ec2Resources {
+    /// Only the attachments and route tables of the transit gateways that were kept, and with
+    /// `--vpc` only the transit gateways attached to those VPCs
+    pub(super) async fn collect_transit_gateway_details(
+        &mut self,
+        progress: &indicatif::ProgressBar,
+    ) -> Result<(), ec2::Error> {
+        let transit_gateway_ids = transit_gateway_ids_of(&self.transit_gateways);
+        if transit_gateway_ids.is_empty() {
+            return Ok(());
+        }
+        progress.inc_length(1);
+        self.transit_gateway_attachments = self
+            .collect_transit_gateway_attachments(&transit_gateway_ids)
+            .await?;
+        progress.set_message("Transit Gateway Attachments");
+        progress.inc(1);
+
+        if !self.all_vpcs {
+            let attached = self
+                .transit_gateway_attachments
+                .iter()
+                .filter_map(|attachment| attachment.transit_gateway_id())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            self.transit_gateways.retain(|transit_gateway| {
+                transit_gateway
+                    .transit_gateway_id()
+                    .map_or(false, |id| attached.iter().any(|attached| attached == id))
+            });
+        }
+        let transit_gateway_ids = transit_gateway_ids_of(&self.transit_gateways);
+        if transit_gateway_ids.is_empty() {
+            return Ok(());
+        }
+        progress.inc_length(1);
+        self.transit_gateway_route_tables = self
+            .collect_transit_gateway_route_tables(&transit_gateway_ids)
+            .await?;
+        progress.set_message("Transit Gateway Route Tables");
+        progress.inc(1);
+
+        self.collect_transit_gateway_routing(progress).await
+    }
+
+    /// VPC attachments name their VPC as the resource
+    async fn collect_transit_gateway_attachments(
+        &self,
+        transit_gateway_ids: &[String],
+    ) -> Result<Vec<ec2::types::TransitGatewayAttachment>, ec2::Error> {
+        let vpc_filter = if self.all_vpcs {
+            None
+        } else {
+            self.resource_vpc_filter()
+        };
+        let transit_gateway_attachments = self
+            .client
+            .describe_transit_gateway_attachments()
+            .filters(filter("transit-gateway-id", transit_gateway_ids))
+            .optionally_filter(vpc_filter)
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(transit_gateway_attachments)
+    }
+
+    async fn collect_transit_gateway_route_tables(
+        &self,
+        transit_gateway_ids: &[String],
+    ) -> Result<Vec<ec2::types::TransitGatewayRouteTable>, ec2::Error> {
+        let transit_gateway_route_tables = self
+            .client
+            .describe_transit_gateway_route_tables()
+            .filters(filter("transit-gateway-id", transit_gateway_ids))
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(transit_gateway_route_tables)
+    }
+
+    /// Associations and propagations can only be fetched one route table at a time
+    pub(super) async fn collect_transit_gateway_routing(
+        &mut self,
+        progress: &indicatif::ProgressBar,
+    ) -> Result<(), ec2::Error> {
+        let route_table_ids = self
+            .transit_gateway_route_tables
+            .iter()
+            .filter_map(|route_table| route_table.transit_gateway_route_table_id())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        progress.inc_length(route_table_ids.len() as u64);
+
+        let this = &*self;
+        let routing = route_table_ids
+            .into_iter()
+            .map(|route_table_id| async move {
+                let (associations, propagations) = tokio::try_join!(
+                    this.requests
+                        .send(this.collect_transit_gateway_associations(&route_table_id)),
+                    this.requests
+                        .send(this.collect_transit_gateway_propagations(&route_table_id)),
+                )?;
+                progress.set_message(route_table_id.clone());
+                progress.inc(1);
+                Ok::<_, ec2::Error>((route_table_id, associations, propagations))
+            });
+        let routing = futures::future::try_join_all(routing).await?;
+
+        for (route_table_id, associations, propagations) in routing {
+            self.transit_gateway_associations
+                .insert(route_table_id.clone(), associations);
+            self.transit_gateway_propagations
+                .insert(route_table_id, propagations);
+        }
+
+        Ok(())
+    }
+
+    async fn collect_transit_gateway_associations(
+        &self,
+        route_table_id: &str,
+    ) -> Result<Vec<ec2::types::TransitGatewayRouteTableAssociation>, ec2::Error> {
+        let associations = self
+            .client
+            .get_transit_gateway_route_table_associations()
+            .transit_gateway_route_table_id(route_table_id)
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(associations)
+    }
+
+    async fn collect_transit_gateway_propagations(
+        &self,
+        route_table_id: &str,
+    ) -> Result<Vec<ec2::types::TransitGatewayRouteTablePropagation>, ec2::Error> {
+        let propagations = self
+            .client
+            .get_transit_gateway_route_table_propagations()
+            .transit_gateway_route_table_id(route_table_id)
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(propagations)
+    }
+
+    pub(super) fn transit_gateway_tree(
+        &self,
+        transit_gateway: &ec2::types::TransitGateway,
+    ) -> ptree::item::StringItem {
+        let state = transit_gateway
+            .state()
+            .map_or("no state", |state| state.as_str());
+        let mut tree =
+            ptree::TreeBuilder::new(format!("{} [{state}]", transit_gateway.id_and_name()));
+        let tree = &mut tree;
+        let transit_gateway_id = transit_gateway.id();
+
+        add_children_with(
+            tree,
+            "Attachments",
+            self.transit_gateway_attachments_of(&transit_gateway_id),
+            |tree, attachment| {
+                tree.add_empty_child(attachment_resource(attachment));
+            },
+        );
+        add_children_with(
+            tree,
+            "Route Tables",
+            self.transit_gateway_route_tables_of(&transit_gateway_id),
+            |tree, route_table| {
+                if route_table
+                    .default_association_route_table()
+                    .unwrap_or_default()
+                {
+                    tree.add_empty_child(String::from("Default association route table"));
+                }
+                if route_table
+                    .default_propagation_route_table()
+                    .unwrap_or_default()
+                {
+                    tree.add_empty_child(String::from("Default propagation route table"));
+                }
+                let route_table_id = route_table.id();
+                let associations = self
+                    .transit_gateway_associations
+                    .get(&route_table_id)
+                    .map(|associations| {
+                        associations
+                            .iter()
+                            .map(|association| {
+                                routing_title(
+                                    association.transit_gateway_attachment_id(),
+                                    association.resource_id(),
+                                    association.state().map(|state| state.as_str()),
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                let propagations = self
+                    .transit_gateway_propagations
+                    .get(&route_table_id)
+                    .map(|propagations| {
+                        propagations
+                            .iter()
+                            .map(|propagation| {
+                                routing_title(
+                                    propagation.transit_gateway_attachment_id(),
+                                    propagation.resource_id(),
+                                    propagation.state().map(|state| state.as_str()),
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                add_titles(tree, "Associations", associations);
+                add_titles(tree, "Propagations", propagations);
+            },
+        );
+
+        tree.build()
+    }
+
+    pub(super) fn transit_gateway_json(
+        &self,
+        transit_gateway: &ec2::types::TransitGateway,
+    ) -> serde_json::Value {
+        let transit_gateway_id = transit_gateway.id();
+        let attachments = self
+            .transit_gateway_attachments_of(&transit_gateway_id)
+            .into_iter()
+            .map(|attachment| {
+                serde_json::json!({
+                    "id": attachment.id(),
+                    "name": attachment.name(),
+                    "resource_type": attachment.resource_type().map(|r#type| r#type.as_str()),
+                    "resource_id": attachment.resource_id(),
+                    "state": attachment.state().map(|state| state.as_str()),
+                })
+            })
+            .collect::<Vec<_>>();
+        let route_tables = self
+            .transit_gateway_route_tables_of(&transit_gateway_id)
+            .into_iter()
+            .map(|route_table| {
+                let route_table_id = route_table.id();
+                let associations = self
+                    .transit_gateway_associations
+                    .get(&route_table_id)
+                    .map(|associations| {
+                        associations
+                            .iter()
+                            .filter_map(|association| association.transit_gateway_attachment_id())
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                let propagations = self
+                    .transit_gateway_propagations
+                    .get(&route_table_id)
+                    .map(|propagations| {
+                        propagations
+                            .iter()
+                            .filter_map(|propagation| propagation.transit_gateway_attachment_id())
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                serde_json::json!({
+                    "id": route_table_id,
+                    "name": route_table.name(),
+                    "associations": associations,
+                    "propagations": propagations,
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "id": transit_gateway_id,
+            "name": transit_gateway.name(),
+            "state": transit_gateway.state().map(|state| state.as_str()),
+            "attachments": attachments,
+            "route_tables": route_tables,
+        })
+    }
+
+    pub(super) fn transit_gateway(
+        &self,
+        transit_gateway_id: &str,
+    ) -> Option<&ec2::types::TransitGateway> {
+        self.transit_gateways.iter().find(|transit_gateway| {
+            transit_gateway.transit_gateway_id() == Some(transit_gateway_id)
+        })
+    }
+
+    pub(super) fn transit_gateway_title(&self, transit_gateway_id: &str) -> String {
+        self.transit_gateway(transit_gateway_id).map_or_else(
+            || transit_gateway_id.to_string(),
+            |transit_gateway| transit_gateway.id_and_name(),
+        )
+    }
+
+    fn transit_gateway_attachments_of(
+        &self,
+        transit_gateway_id: &str,
+    ) -> Vec<&ec2::types::TransitGatewayAttachment> {
+        let transit_gateway_id = Some(transit_gateway_id);
+        self.transit_gateway_attachments
+            .iter()
+            .filter(|attachment| attachment.transit_gateway_id() == transit_gateway_id)
+            .collect()
+    }
+
+    fn transit_gateway_route_tables_of(
+        &self,
+        transit_gateway_id: &str,
+    ) -> Vec<&ec2::types::TransitGatewayRouteTable> {
+        let transit_gateway_id = Some(transit_gateway_id);
+        self.transit_gateway_route_tables
+            .iter()
+            .filter(|route_table| route_table.transit_gateway_id() == transit_gateway_id)
+            .collect()
+    }
+}
+
+fn attachment_resource(attachment: &ec2::types::TransitGatewayAttachment) -> String {
+    let r#type = attachment
+        .resource_type()
+        .map_or("unknown", |r#type| r#type.as_str());
+    let resource_id = attachment.resource_id().unwrap_or("no resource");
+    let state = attachment
+        .state()
+        .map_or("no state", |state| state.as_str());
+    format!("{type} {resource_id} [{state}]")
+}
+
+fn routing_title(
+    attachment_id: Option<&str>,
+    resource_id: Option<&str>,
+    state: Option<&str>,
+) -> String {
+    let attachment_id = attachment_id.unwrap_or("no attachment");
+    let resource_id = resource_id.unwrap_or("no resource");
+    let state = state.unwrap_or("no state");
+    format!("{attachment_id}: {resource_id} [{state}]")
+}
+
+fn add_titles(tree: &mut ptree::TreeBuilder, title: &str, titles: Vec<String>) {
+    if !titles.is_empty() {
+        tree.begin_child(title.to_string());
+        for title in titles {
+            tree.add_empty_child(title);
+        }
+        tree.end_child();
+    }
+}
+
+fn transit_gateway_ids_of(transit_gateways: &[ec2::types::TransitGateway]) -> Vec<String> {
+    transit_gateways
+        .iter()
+        .filter_map(|transit_gateway| transit_gateway.transit_gateway_id())
+        .map(ToString::to_string)
+        .collect()
+}
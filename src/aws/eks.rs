@@ -0,0 +1,387 @@
+use std::collections::BTreeSet;
+
+use aws_sdk_eks as eks;
+use tokio_stream::StreamExt;
+
+use crate::aws::{add_detail, add_list, Requests};
+use crate::output::Render;
+use crate::Show;
+
+#[derive(Debug)]
+pub(crate) struct EksResources {
+    client: eks::Client,
+    requests: Requests,
+    clusters: Vec<Cluster>,
+}
+
+/// A cluster together with everything that can only be listed per cluster
+#[derive(Debug)]
+struct Cluster {
+    cluster: eks::types::Cluster,
+    nodegroups: Vec<eks::types::Nodegroup>,
+    fargate_profiles: Vec<eks::types::FargateProfile>,
+    addons: Vec<eks::types::Addon>,
+}
+
+impl EksResources {
+    pub(crate) fn new(config: &aws_types::SdkConfig) -> Self {
+        let client = eks::Client::new(config);
+
+        Self {
+            client,
+            requests: Requests::new(),
+            clusters: vec![],
+        }
+    }
+
+    pub(crate) async fn collect(
+        &mut self,
+        clusters: &[String],
+        vpcs: &[String],
+        progress: &indicatif::ProgressBar,
+    ) -> Result<(), eks::Error> {
+        progress.inc_length(1);
+        progress.set_message("Clusters");
+        let requested = clusters.iter().map(String::as_str).collect::<BTreeSet<_>>();
+        let names = self
+            .client
+            .list_clusters()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?
+            .into_iter()
+            .filter(|name| requested.is_empty() || requested.contains(name.as_str()))
+            .collect::<Vec<_>>();
+        progress.inc(1);
+        progress.inc_length(names.len() as u64);
+
+        // Everything below a cluster can only be fetched one cluster at a time. Only the requests
+        // themselves wait for `requests`, so the clusters do not hold it while they fan out.
+        let this = &*self;
+        let clusters = futures::future::try_join_all(names.iter().map(|name| async move {
+            let cluster = this.collect_cluster(name).await?;
+            progress.set_message(name.clone());
+            progress.inc(1);
+            Ok::<_, eks::Error>(cluster)
+        }))
+        .await?;
+
+        let vpcs = vpcs.iter().map(String::as_str).collect::<BTreeSet<_>>();
+        self.clusters = clusters
+            .into_iter()
+            .flatten()
+            .filter(|cluster| {
+                vpcs.is_empty()
+                    || cluster
+                        .cluster
+                        .resources_vpc_config()
+                        .and_then(|config| config.vpc_id())
+                        .map_or(false, |vpc_id| vpcs.contains(vpc_id))
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    async fn collect_cluster(&self, name: &str) -> Result<Option<Cluster>, eks::Error> {
+        let cluster = self
+            .requests
+            .send(self.client.describe_cluster().name(name).send())
+            .await?
+            .cluster;
+        let cluster = match cluster {
+            Some(cluster) => cluster,
+            None => return Ok(None),
+        };
+
+        let (nodegroups, fargate_profiles, addons) = tokio::try_join!(
+            self.collect_nodegroups(name),
+            self.collect_fargate_profiles(name),
+            self.collect_addons(name),
+        )?;
+
+        Ok(Some(Cluster {
+            cluster,
+            nodegroups,
+            fargate_profiles,
+            addons,
+        }))
+    }
+
+    async fn collect_nodegroups(
+        &self,
+        cluster: &str,
+    ) -> Result<Vec<eks::types::Nodegroup>, eks::Error> {
+        let names = self
+            .requests
+            .send(
+                self.client
+                    .list_nodegroups()
+                    .cluster_name(cluster)
+                    .into_paginator()
+                    .items()
+                    .send()
+                    .collect::<Result<Vec<_>, _>>(),
+            )
+            .await?;
+        let nodegroups = self
+            .requests
+            .send_each(names, |name| {
+                self.client
+                    .describe_nodegroup()
+                    .cluster_name(cluster)
+                    .nodegroup_name(name)
+                    .send()
+            })
+            .await?
+            .into_iter()
+            .filter_map(|output| output.nodegroup)
+            .collect();
+
+        Ok(nodegroups)
+    }
+
+    async fn collect_fargate_profiles(
+        &self,
+        cluster: &str,
+    ) -> Result<Vec<eks::types::FargateProfile>, eks::Error> {
+        let names = self
+            .requests
+            .send(
+                self.client
+                    .list_fargate_profiles()
+                    .cluster_name(cluster)
+                    .into_paginator()
+                    .items()
+                    .send()
+                    .collect::<Result<Vec<_>, _>>(),
+            )
+            .await?;
+        let fargate_profiles = self
+            .requests
+            .send_each(names, |name| {
+                self.client
+                    .describe_fargate_profile()
+                    .cluster_name(cluster)
+                    .fargate_profile_name(name)
+                    .send()
+            })
+            .await?
+            .into_iter()
+            .filter_map(|output| output.fargate_profile)
+            .collect();
+
+        Ok(fargate_profiles)
+    }
+
+    async fn collect_addons(&self, cluster: &str) -> Result<Vec<eks::types::Addon>, eks::Error> {
+        let names = self
+            .requests
+            .send(
+                self.client
+                    .list_addons()
+                    .cluster_name(cluster)
+                    .into_paginator()
+                    .items()
+                    .send()
+                    .collect::<Result<Vec<_>, _>>(),
+            )
+            .await?;
+        let addons = self
+            .requests
+            .send_each(names, |name| {
+                self.client
+                    .describe_addon()
+                    .cluster_name(cluster)
+                    .addon_name(name)
+                    .send()
+            })
+            .await?
+            .into_iter()
+            .filter_map(|output| output.addon)
+            .collect();
+
+        Ok(addons)
+    }
+}
+
+impl Render for EksResources {
+    fn trees(&self) -> Vec<ptree::item::StringItem> {
+        self.clusters.iter().map(cluster_tree).collect()
+    }
+
+    fn json(&self) -> serde_json::Value {
+        let clusters = self.clusters.iter().map(cluster_json).collect::<Vec<_>>();
+        serde_json::json!({ "clusters": clusters })
+    }
+}
+
+fn cluster_tree(cluster: &Cluster) -> ptree::item::StringItem {
+    let Cluster {
+        cluster,
+        nodegroups,
+        fargate_profiles,
+        addons,
+    } = cluster;
+    let version = cluster.version().unwrap_or("no version");
+    let status = cluster
+        .status()
+        .map_or("no status", |status| status.as_str());
+    let mut tree =
+        ptree::TreeBuilder::new(format!("{} ({version}) [{status}]", Show::id(&cluster)));
+    let tree = &mut tree;
+
+    add_detail(tree, "Endpoint", cluster.endpoint());
+    add_detail(tree, "Platform Version", cluster.platform_version());
+    if let Some(config) = cluster.resources_vpc_config() {
+        add_detail(tree, "VPC", config.vpc_id());
+        add_detail(
+            tree,
+            "Cluster Security Group",
+            config.cluster_security_group_id(),
+        );
+        add_list(tree, "Subnets", config.subnet_ids().unwrap_or_default());
+    }
+
+    if !nodegroups.is_empty() {
+        tree.begin_child(String::from("Node Groups"));
+        for nodegroup in nodegroups {
+            tree.begin_child(nodegroup_title(nodegroup));
+            if let Some(scaling) = nodegroup.scaling_config() {
+                tree.add_empty_child(format!(
+                    "Scaling: desired {}, min {}, max {}",
+                    scaling.desired_size().unwrap_or_default(),
+                    scaling.min_size().unwrap_or_default(),
+                    scaling.max_size().unwrap_or_default(),
+                ));
+            }
+            add_detail(tree, "Release Version", nodegroup.release_version());
+            add_list(tree, "Subnets", nodegroup.subnets().unwrap_or_default());
+            tree.end_child();
+        }
+        tree.end_child();
+    }
+
+    if !fargate_profiles.is_empty() {
+        tree.begin_child(String::from("Fargate Profiles"));
+        for fargate_profile in fargate_profiles {
+            let status = fargate_profile
+                .status()
+                .map_or("no status", |status| status.as_str());
+            tree.begin_child(format!("{} [{status}]", fargate_profile.id()));
+            let namespaces = fargate_profile
+                .selectors()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|selector| selector.namespace())
+                .collect::<Vec<_>>()
+                .join(", ");
+            if !namespaces.is_empty() {
+                tree.add_empty_child(format!("Namespaces: {namespaces}"));
+            }
+            add_list(
+                tree,
+                "Subnets",
+                fargate_profile.subnets().unwrap_or_default(),
+            );
+            tree.end_child();
+        }
+        tree.end_child();
+    }
+
+    if !addons.is_empty() {
+        tree.begin_child(String::from("Add-ons"));
+        for addon in addons {
+            let version = addon.addon_version().unwrap_or("no version");
+            let status = addon.status().map_or("no status", |status| status.as_str());
+            tree.add_empty_child(format!("{} {version} [{status}]", addon.id()));
+        }
+        tree.end_child();
+    }
+
+    tree.build()
+}
+
+fn cluster_json(cluster: &Cluster) -> serde_json::Value {
+    let Cluster {
+        cluster,
+        nodegroups,
+        fargate_profiles,
+        addons,
+    } = cluster;
+    let config = cluster.resources_vpc_config();
+    let nodegroups = nodegroups
+        .iter()
+        .map(|nodegroup| {
+            let scaling = nodegroup.scaling_config();
+            serde_json::json!({
+                "id": nodegroup.id(),
+                "name": nodegroup.name(),
+                "status": nodegroup.status().map(|status| status.as_str()),
+                "capacity_type": nodegroup.capacity_type().map(|r#type| r#type.as_str()),
+                "instance_types": nodegroup.instance_types(),
+                "desired_size": scaling.and_then(|scaling| scaling.desired_size()),
+                "min_size": scaling.and_then(|scaling| scaling.min_size()),
+                "max_size": scaling.and_then(|scaling| scaling.max_size()),
+                "subnets": nodegroup.subnets(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let fargate_profiles = fargate_profiles
+        .iter()
+        .map(|fargate_profile| {
+            let namespaces = fargate_profile
+                .selectors()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|selector| selector.namespace())
+                .collect::<Vec<_>>();
+            serde_json::json!({
+                "id": fargate_profile.id(),
+                "name": fargate_profile.name(),
+                "status": fargate_profile.status().map(|status| status.as_str()),
+                "namespaces": namespaces,
+                "subnets": fargate_profile.subnets(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let addons = addons
+        .iter()
+        .map(|addon| {
+            serde_json::json!({
+                "id": addon.id(),
+                "name": addon.name(),
+                "version": addon.addon_version(),
+                "status": addon.status().map(|status| status.as_str()),
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "id": Show::id(&cluster),
+        "name": Show::name(&cluster),
+        "version": cluster.version(),
+        "status": cluster.status().map(|status| status.as_str()),
+        "endpoint": cluster.endpoint(),
+        "vpc_id": config.and_then(|config| config.vpc_id()),
+        "cluster_security_group_id": config.and_then(|config| config.cluster_security_group_id()),
+        "nodegroups": nodegroups,
+        "fargate_profiles": fargate_profiles,
+        "addons": addons,
+    })
+}
+
+fn nodegroup_title(nodegroup: &eks::types::Nodegroup) -> String {
+    let capacity_type = nodegroup
+        .capacity_type()
+        .map_or("unknown capacity", |r#type| r#type.as_str());
+    let instance_types = nodegroup.instance_types().unwrap_or_default().join(", ");
+    let status = nodegroup
+        .status()
+        .map_or("no status", |status| status.as_str());
+    format!(
+        "{} ({capacity_type}, {instance_types}) [{status}]",
+        nodegroup.id()
+    )
+}
@@ -0,0 +1,698 @@
+use std::collections::{BTreeSet, HashMap};
+
+use aws_sdk_elasticloadbalancing as elb;
+use aws_sdk_elasticloadbalancingv2 as elbv2;
+use futures::TryFutureExt;
+use tokio_stream::StreamExt;
+
+use crate::aws::{add_detail, has_tags, Requests};
+use crate::output::Render;
+use crate::Show;
+
+/// DescribeTags accepts at most 20 load balancers per call
+const TAGS_PER_CALL: usize = 20;
+
+#[derive(Debug)]
+pub(crate) struct ElbResources {
+    client: elbv2::Client,
+    classic_client: elb::Client,
+    requests: Requests,
+    tags: Vec<(String, String)>,
+    load_balancers: Vec<elbv2::types::LoadBalancer>,
+    classic_load_balancers: Vec<elb::types::LoadBalancerDescription>,
+    target_groups: Vec<elbv2::types::TargetGroup>,
+    listeners: HashMap<String, Vec<elbv2::types::Listener>>,
+    target_health: HashMap<String, Vec<elbv2::types::TargetHealthDescription>>,
+}
+
+impl ElbResources {
+    pub(crate) fn new(config: &aws_types::SdkConfig, tags: &[(String, String)]) -> Self {
+        let client = elbv2::Client::new(config);
+        let classic_client = elb::Client::new(config);
+        let tags = tags.to_vec();
+        Self {
+            client,
+            classic_client,
+            requests: Requests::new(),
+            tags,
+            load_balancers: vec![],
+            classic_load_balancers: vec![],
+            target_groups: vec![],
+            listeners: HashMap::new(),
+            target_health: HashMap::new(),
+        }
+    }
+
+    /// Collect the load balancers of the given VPCs (or all of them) and everything behind them
+    pub(crate) async fn collect(
+        &mut self,
+        vpcs: &[String],
+        progress: &indicatif::ProgressBar,
+    ) -> anyhow::Result<()> {
+        progress.inc_length(3);
+        progress.set_message("Load Balancers");
+
+        let (load_balancers, classic_load_balancers, target_groups) = tokio::try_join!(
+            self.collect_load_balancers().err_into::<anyhow::Error>(),
+            self.collect_classic_load_balancers().err_into(),
+            self.collect_target_groups().err_into(),
+        )?;
+        progress.inc(3);
+
+        let vpcs = vpcs.iter().map(String::as_str).collect::<BTreeSet<_>>();
+        let in_vpcs = |vpc_id: Option<&str>| {
+            vpcs.is_empty() || vpc_id.map_or(false, |vpc_id| vpcs.contains(vpc_id))
+        };
+        self.load_balancers = load_balancers
+            .into_iter()
+            .filter(|load_balancer| in_vpcs(load_balancer.vpc_id()))
+            .collect();
+        self.classic_load_balancers = classic_load_balancers
+            .into_iter()
+            .filter(|load_balancer| in_vpcs(load_balancer.vpc_id()))
+            .collect();
+
+        if !self.tags.is_empty() {
+            let (tagged, classic_tagged) = tokio::try_join!(
+                self.collect_tagged_load_balancers()
+                    .err_into::<anyhow::Error>(),
+                self.collect_tagged_classic_load_balancers().err_into(),
+            )?;
+            self.load_balancers.retain(|load_balancer| {
+                load_balancer
+                    .load_balancer_arn()
+                    .map_or(false, |arn| tagged.contains(arn))
+            });
+            self.classic_load_balancers.retain(|load_balancer| {
+                load_balancer
+                    .load_balancer_name()
+                    .map_or(false, |name| classic_tagged.contains(name))
+            });
+        }
+
+        let load_balancer_arns = self
+            .load_balancers
+            .iter()
+            .filter_map(|load_balancer| load_balancer.load_balancer_arn())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        self.target_groups = target_groups
+            .into_iter()
+            .filter(|target_group| {
+                target_group
+                    .load_balancer_arns()
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|arn| load_balancer_arns.contains(arn))
+            })
+            .collect();
+        let target_group_arns = self
+            .target_groups
+            .iter()
+            .filter_map(|target_group| target_group.target_group_arn())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        progress.inc_length((load_balancer_arns.len() + target_group_arns.len()) as u64);
+
+        // Listeners and target health are fetched one load balancer / target group at a time
+        let this = &*self;
+        let (listeners, target_health) = tokio::try_join!(
+            this.requests
+                .send_each(load_balancer_arns, |arn| async move {
+                    let listeners = this.collect_listeners(&arn).await?;
+                    progress.inc(1);
+                    Ok::<_, elbv2::Error>((arn, listeners))
+                }),
+            this.requests
+                .send_each(target_group_arns, |arn| async move {
+                    let target_health = this.collect_target_health(&arn).await?;
+                    progress.inc(1);
+                    Ok::<_, elbv2::Error>((arn, target_health))
+                }),
+        )?;
+
+        self.listeners = listeners.into_iter().collect();
+        self.target_health = target_health.into_iter().collect();
+
+        Ok(())
+    }
+
+    async fn collect_load_balancers(
+        &self,
+    ) -> Result<Vec<elbv2::types::LoadBalancer>, elbv2::Error> {
+        let load_balancers = self
+            .client
+            .describe_load_balancers()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(load_balancers)
+    }
+
+    async fn collect_classic_load_balancers(
+        &self,
+    ) -> Result<Vec<elb::types::LoadBalancerDescription>, elb::Error> {
+        let load_balancers = self
+            .classic_client
+            .describe_load_balancers()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(load_balancers)
+    }
+
+    async fn collect_target_groups(&self) -> Result<Vec<elbv2::types::TargetGroup>, elbv2::Error> {
+        let target_groups = self
+            .client
+            .describe_target_groups()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(target_groups)
+    }
+
+    async fn collect_listeners(
+        &self,
+        load_balancer_arn: &str,
+    ) -> Result<Vec<elbv2::types::Listener>, elbv2::Error> {
+        let listeners = self
+            .client
+            .describe_listeners()
+            .load_balancer_arn(load_balancer_arn)
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(listeners)
+    }
+
+    async fn collect_target_health(
+        &self,
+        target_group_arn: &str,
+    ) -> Result<Vec<elbv2::types::TargetHealthDescription>, elbv2::Error> {
+        let target_health = self
+            .client
+            .describe_target_health()
+            .target_group_arn(target_group_arn)
+            .send()
+            .await?
+            .target_health_descriptions
+            .unwrap_or_default();
+
+        Ok(target_health)
+    }
+
+    /// ARNs of the load balancers carrying all the requested tags
+    async fn collect_tagged_load_balancers(&self) -> Result<BTreeSet<String>, elbv2::Error> {
+        let arns = self
+            .load_balancers
+            .iter()
+            .filter_map(|load_balancer| load_balancer.load_balancer_arn())
+            .collect::<Vec<_>>();
+        let mut tagged = BTreeSet::new();
+        for arns in arns.chunks(TAGS_PER_CALL) {
+            let tag_descriptions = arns
+                .iter()
+                .fold(self.client.describe_tags(), |describe, arn| {
+                    describe.resource_arns(*arn)
+                })
+                .send()
+                .await?
+                .tag_descriptions
+                .unwrap_or_default();
+            tagged.extend(
+                tag_descriptions
+                    .into_iter()
+                    .filter(|description| has_tags(&self.tags, description.tags()))
+                    .filter_map(|description| description.resource_arn),
+            );
+        }
+
+        Ok(tagged)
+    }
+
+    /// Names of the classic load balancers carrying all the requested tags
+    async fn collect_tagged_classic_load_balancers(&self) -> Result<BTreeSet<String>, elb::Error> {
+        let names = self
+            .classic_load_balancers
+            .iter()
+            .filter_map(|load_balancer| load_balancer.load_balancer_name())
+            .collect::<Vec<_>>();
+        let mut tagged = BTreeSet::new();
+        for names in names.chunks(TAGS_PER_CALL) {
+            let tag_descriptions = names
+                .iter()
+                .fold(self.classic_client.describe_tags(), |describe, name| {
+                    describe.load_balancer_names(*name)
+                })
+                .send()
+                .await?
+                .tag_descriptions
+                .unwrap_or_default();
+            tagged.extend(
+                tag_descriptions
+                    .into_iter()
+                    .filter(|description| has_tags(&self.tags, description.tags()))
+                    .filter_map(|description| description.load_balancer_name),
+            );
+        }
+
+        Ok(tagged)
+    }
+
+    /// Add a "Load Balancers" node with the load balancers of this VPC, if there are any
+    pub(crate) fn add_vpc_load_balancers(&self, tree: &mut ptree::TreeBuilder, vpc_id: &str) {
+        let load_balancers = self.load_balancers_of(vpc_id);
+        let classic_load_balancers = self.classic_load_balancers_of(vpc_id);
+        if !load_balancers.is_empty() || !classic_load_balancers.is_empty() {
+            tree.begin_child(String::from("Load Balancers"));
+            for load_balancer in load_balancers {
+                tree.begin_child(load_balancer_title(load_balancer));
+                self.load_balancer_details(tree, load_balancer);
+                tree.end_child();
+            }
+            for load_balancer in classic_load_balancers {
+                tree.begin_child(classic_load_balancer_title(load_balancer));
+                classic_load_balancer_details(tree, load_balancer);
+                tree.end_child();
+            }
+            tree.end_child();
+        }
+    }
+
+    pub(crate) fn vpc_json(&self, vpc_id: &str) -> Vec<serde_json::Value> {
+        let load_balancers = self
+            .load_balancers_of(vpc_id)
+            .into_iter()
+            .map(|load_balancer| self.load_balancer_json(load_balancer));
+        let classic_load_balancers = self
+            .classic_load_balancers_of(vpc_id)
+            .into_iter()
+            .map(classic_load_balancer_json);
+        load_balancers.chain(classic_load_balancers).collect()
+    }
+
+    /// Names and subnets of the load balancers of this VPC, for the graph outputs
+    pub(crate) fn vpc_subnets(&self, vpc_id: &str) -> Vec<(String, Vec<&str>)> {
+        let load_balancers = self
+            .load_balancers_of(vpc_id)
+            .into_iter()
+            .map(|load_balancer| {
+                let subnets = load_balancer
+                    .availability_zones()
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|zone| zone.subnet_id())
+                    .collect();
+                (load_balancer.id(), subnets)
+            });
+        let classic_load_balancers =
+            self.classic_load_balancers_of(vpc_id)
+                .into_iter()
+                .map(|load_balancer| {
+                    let subnets = load_balancer
+                        .subnets()
+                        .unwrap_or_default()
+                        .iter()
+                        .map(String::as_str)
+                        .collect();
+                    (load_balancer.id(), subnets)
+                });
+        load_balancers.chain(classic_load_balancers).collect()
+    }
+
+    fn load_balancers_of(&self, vpc_id: &str) -> Vec<&elbv2::types::LoadBalancer> {
+        let vpc_id = Some(vpc_id);
+        self.load_balancers
+            .iter()
+            .filter(|load_balancer| load_balancer.vpc_id() == vpc_id)
+            .collect()
+    }
+
+    fn classic_load_balancers_of(&self, vpc_id: &str) -> Vec<&elb::types::LoadBalancerDescription> {
+        let vpc_id = Some(vpc_id);
+        self.classic_load_balancers
+            .iter()
+            .filter(|load_balancer| load_balancer.vpc_id() == vpc_id)
+            .collect()
+    }
+
+    fn listeners_of(
+        &self,
+        load_balancer: &elbv2::types::LoadBalancer,
+    ) -> &[elbv2::types::Listener] {
+        load_balancer
+            .load_balancer_arn()
+            .and_then(|arn| self.listeners.get(arn))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn target_groups_of(
+        &self,
+        load_balancer: &elbv2::types::LoadBalancer,
+    ) -> Vec<&elbv2::types::TargetGroup> {
+        let load_balancer_arn = load_balancer.load_balancer_arn().unwrap_or_default();
+        self.target_groups
+            .iter()
+            .filter(|target_group| {
+                target_group
+                    .load_balancer_arns()
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|arn| arn == load_balancer_arn)
+            })
+            .collect()
+    }
+
+    fn targets_of(
+        &self,
+        target_group: &elbv2::types::TargetGroup,
+    ) -> &[elbv2::types::TargetHealthDescription] {
+        target_group
+            .target_group_arn()
+            .and_then(|arn| self.target_health.get(arn))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Name of the target group with the given ARN, or the ARN itself
+    fn target_group_name(&self, target_group_arn: &str) -> String {
+        self.target_groups
+            .iter()
+            .find(|target_group| target_group.target_group_arn() == Some(target_group_arn))
+            .map_or_else(
+                || target_group_arn.to_string(),
+                |target_group| target_group.id(),
+            )
+    }
+
+    fn load_balancer_tree(
+        &self,
+        load_balancer: &elbv2::types::LoadBalancer,
+    ) -> ptree::item::StringItem {
+        let mut tree = ptree::TreeBuilder::new(load_balancer_title(load_balancer));
+        self.load_balancer_details(&mut tree, load_balancer);
+        tree.build()
+    }
+
+    fn load_balancer_details(
+        &self,
+        tree: &mut ptree::TreeBuilder,
+        load_balancer: &elbv2::types::LoadBalancer,
+    ) {
+        add_detail(tree, "DNS Name", load_balancer.dns_name());
+        add_detail(tree, "VPC", load_balancer.vpc_id());
+
+        let listeners = self.listeners_of(load_balancer);
+        if !listeners.is_empty() {
+            tree.begin_child(String::from("Listeners"));
+            for listener in listeners {
+                tree.add_empty_child(self.listener_title(listener));
+            }
+            tree.end_child();
+        }
+
+        let target_groups = self.target_groups_of(load_balancer);
+        if !target_groups.is_empty() {
+            tree.begin_child(String::from("Target Groups"));
+            for target_group in target_groups {
+                tree.begin_child(target_group_title(target_group));
+                for target in self.targets_of(target_group) {
+                    tree.add_empty_child(target_title(target));
+                }
+                tree.end_child();
+            }
+            tree.end_child();
+        }
+    }
+
+    fn listener_title(&self, listener: &elbv2::types::Listener) -> String {
+        let protocol = listener
+            .protocol()
+            .map_or("no protocol", |protocol| protocol.as_str());
+        let port = listener.port().unwrap_or_default();
+        let actions = listener
+            .default_actions()
+            .unwrap_or_default()
+            .iter()
+            .map(|action| self.action_title(action))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{protocol}:{port} → {actions}")
+    }
+
+    fn action_title(&self, action: &elbv2::types::Action) -> String {
+        if let Some(target_group_arn) = action.target_group_arn() {
+            return self.target_group_name(target_group_arn);
+        }
+        if let Some(forward) = action.forward_config() {
+            return forward
+                .target_groups()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|target_group| target_group.target_group_arn())
+                .map(|arn| self.target_group_name(arn))
+                .collect::<Vec<_>>()
+                .join(" + ");
+        }
+        if let Some(redirect) = action.redirect_config() {
+            let protocol = redirect.protocol().unwrap_or("#{protocol}");
+            let host = redirect.host().unwrap_or("#{host}");
+            let port = redirect.port().unwrap_or("#{port}");
+            return format!("redirect {protocol}://{host}:{port}");
+        }
+        action
+            .r#type()
+            .map_or("no action", |r#type| r#type.as_str())
+            .to_string()
+    }
+
+    fn load_balancer_json(&self, load_balancer: &elbv2::types::LoadBalancer) -> serde_json::Value {
+        let listeners = self
+            .listeners_of(load_balancer)
+            .iter()
+            .map(|listener| {
+                serde_json::json!({
+                    "protocol": listener.protocol().map(|protocol| protocol.as_str()),
+                    "port": listener.port(),
+                    "default_actions": listener
+                        .default_actions()
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|action| self.action_title(action))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let target_groups = self
+            .target_groups_of(load_balancer)
+            .into_iter()
+            .map(|target_group| {
+                let targets = self
+                    .targets_of(target_group)
+                    .iter()
+                    .map(|target| {
+                        serde_json::json!({
+                            "id": target.target().and_then(|target| target.id()),
+                            "port": target.target().and_then(|target| target.port()),
+                            "health": target
+                                .target_health()
+                                .and_then(|health| health.state())
+                                .map(|state| state.as_str()),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                serde_json::json!({
+                    "id": target_group.target_group_arn(),
+                    "name": target_group.target_group_name(),
+                    "protocol": target_group.protocol().map(|protocol| protocol.as_str()),
+                    "port": target_group.port(),
+                    "target_type": target_group.target_type().map(|r#type| r#type.as_str()),
+                    "targets": targets,
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "id": load_balancer.load_balancer_arn(),
+            "name": load_balancer.load_balancer_name(),
+            "type": load_balancer.r#type().map(|r#type| r#type.as_str()),
+            "scheme": load_balancer.scheme().map(|scheme| scheme.as_str()),
+            "state": load_balancer
+                .state()
+                .and_then(|state| state.code())
+                .map(|code| code.as_str()),
+            "dns_name": load_balancer.dns_name(),
+            "vpc_id": load_balancer.vpc_id(),
+            "listeners": listeners,
+            "target_groups": target_groups,
+        })
+    }
+}
+
+impl Render for ElbResources {
+    fn trees(&self) -> Vec<ptree::item::StringItem> {
+        let load_balancers = self
+            .load_balancers
+            .iter()
+            .map(|load_balancer| self.load_balancer_tree(load_balancer));
+        let classic_load_balancers = self
+            .classic_load_balancers
+            .iter()
+            .map(classic_load_balancer_tree);
+        load_balancers.chain(classic_load_balancers).collect()
+    }
+
+    fn json(&self) -> serde_json::Value {
+        let load_balancers = self
+            .load_balancers
+            .iter()
+            .map(|load_balancer| self.load_balancer_json(load_balancer));
+        let classic_load_balancers = self
+            .classic_load_balancers
+            .iter()
+            .map(classic_load_balancer_json);
+        let load_balancers = load_balancers
+            .chain(classic_load_balancers)
+            .collect::<Vec<_>>();
+        serde_json::json!({ "load_balancers": load_balancers })
+    }
+}
+
+fn load_balancer_title(load_balancer: &elbv2::types::LoadBalancer) -> String {
+    let r#type = load_balancer
+        .r#type()
+        .map_or("unknown", |r#type| r#type.as_str());
+    let scheme = load_balancer
+        .scheme()
+        .map_or("no scheme", |scheme| scheme.as_str());
+    let state = load_balancer
+        .state()
+        .and_then(|state| state.code())
+        .map_or("no state", |code| code.as_str());
+    format!("{} ({type}, {scheme}) [{state}]", load_balancer.id())
+}
+
+fn target_group_title(target_group: &elbv2::types::TargetGroup) -> String {
+    let protocol = target_group
+        .protocol()
+        .map_or("no protocol", |protocol| protocol.as_str());
+    let port = target_group.port().unwrap_or_default();
+    let r#type = target_group
+        .target_type()
+        .map_or("unknown", |r#type| r#type.as_str());
+    format!("{} ({protocol}:{port}, {type})", target_group.id())
+}
+
+fn target_title(target: &elbv2::types::TargetHealthDescription) -> String {
+    let id = target
+        .target()
+        .and_then(|target| target.id())
+        .unwrap_or("no id");
+    let health = target
+        .target_health()
+        .and_then(|health| health.state())
+        .map_or("unknown", |state| state.as_str());
+    match target.target().and_then(|target| target.port()) {
+        Some(port) => format!("{id}:{port} [{health}]"),
+        None => format!("{id} [{health}]"),
+    }
+}
+
+fn classic_load_balancer_title(load_balancer: &elb::types::LoadBalancerDescription) -> String {
+    let scheme = load_balancer.scheme().unwrap_or("no scheme");
+    format!("{} (classic, {scheme})", load_balancer.id())
+}
+
+fn classic_load_balancer_tree(
+    load_balancer: &elb::types::LoadBalancerDescription,
+) -> ptree::item::StringItem {
+    let mut tree = ptree::TreeBuilder::new(classic_load_balancer_title(load_balancer));
+    classic_load_balancer_details(&mut tree, load_balancer);
+    tree.build()
+}
+
+fn classic_load_balancer_details(
+    tree: &mut ptree::TreeBuilder,
+    load_balancer: &elb::types::LoadBalancerDescription,
+) {
+    add_detail(tree, "DNS Name", load_balancer.dns_name());
+    add_detail(tree, "VPC", load_balancer.vpc_id());
+
+    let listeners = load_balancer
+        .listener_descriptions()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|description| description.listener())
+        .collect::<Vec<_>>();
+    if !listeners.is_empty() {
+        tree.begin_child(String::from("Listeners"));
+        for listener in listeners {
+            tree.add_empty_child(classic_listener_title(listener));
+        }
+        tree.end_child();
+    }
+
+    let instances = load_balancer.instances().unwrap_or_default();
+    if !instances.is_empty() {
+        tree.begin_child(String::from("Instances"));
+        for instance in instances {
+            tree.add_empty_child(instance.instance_id().unwrap_or("no id").to_string());
+        }
+        tree.end_child();
+    }
+}
+
+fn classic_listener_title(listener: &elb::types::Listener) -> String {
+    let protocol = listener.protocol().unwrap_or("no protocol");
+    let port = listener.load_balancer_port();
+    let instance_protocol = listener.instance_protocol().unwrap_or(protocol);
+    let instance_port = listener.instance_port();
+    format!("{protocol}:{port} → {instance_protocol}:{instance_port}")
+}
+
+fn classic_load_balancer_json(
+    load_balancer: &elb::types::LoadBalancerDescription,
+) -> serde_json::Value {
+    let listeners = load_balancer
+        .listener_descriptions()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|description| description.listener())
+        .map(|listener| {
+            serde_json::json!({
+                "protocol": listener.protocol(),
+                "port": listener.load_balancer_port(),
+                "instance_protocol": listener.instance_protocol(),
+                "instance_port": listener.instance_port(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let instances = load_balancer
+        .instances()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|instance| instance.instance_id())
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "id": load_balancer.load_balancer_name(),
+        "name": load_balancer.load_balancer_name(),
+        "type": "classic",
+        "scheme": load_balancer.scheme(),
+        "dns_name": load_balancer.dns_name(),
+        "vpc_id": load_balancer.vpc_id(),
+        "listeners": listeners,
+        "instances": instances,
+    })
+}
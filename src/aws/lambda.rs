@@ -0,0 +1,294 @@
+use std::collections::BTreeSet;
+
+use aws_sdk_lambda as lambda;
+use tokio_stream::StreamExt;
+
+use crate::aws::add_list;
+use crate::output::Render;
+use crate::Show;
+
+#[derive(Debug)]
+pub(crate) struct LambdaResources {
+    client: lambda::Client,
+    functions: Vec<lambda::types::FunctionConfiguration>,
+    event_source_mappings: Vec<lambda::types::EventSourceMappingConfiguration>,
+}
+
+impl LambdaResources {
+    pub(crate) fn new(config: &aws_types::SdkConfig) -> Self {
+        let client = lambda::Client::new(config);
+
+        Self {
+            client,
+            functions: vec![],
+            event_source_mappings: vec![],
+        }
+    }
+
+    /// Collect the functions attached to the given VPCs (or all of them)
+    pub(crate) async fn collect_functions(
+        &mut self,
+        vpcs: &[String],
+        progress: &indicatif::ProgressBar,
+    ) -> Result<(), lambda::Error> {
+        progress.inc_length(1);
+        progress.set_message("Lambda Functions");
+
+        let vpcs = vpcs.iter().map(String::as_str).collect::<BTreeSet<_>>();
+        self.functions = self
+            .client
+            .list_functions()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?
+            .into_iter()
+            .filter(|function| {
+                vpcs.is_empty() || function_vpc(function).map_or(false, |vpc| vpcs.contains(vpc))
+            })
+            .collect();
+        progress.inc(1);
+
+        Ok(())
+    }
+
+    /// Collect the functions and the event source mappings that invoke them
+    pub(crate) async fn collect(
+        &mut self,
+        vpcs: &[String],
+        progress: &indicatif::ProgressBar,
+    ) -> Result<(), lambda::Error> {
+        self.collect_functions(vpcs, progress).await?;
+
+        progress.inc_length(1);
+        progress.set_message("Event Source Mappings");
+        let functions = self
+            .functions
+            .iter()
+            .filter_map(|function| function.function_arn())
+            .collect::<BTreeSet<_>>();
+        self.event_source_mappings = self
+            .client
+            .list_event_source_mappings()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?
+            .into_iter()
+            .filter(|mapping| {
+                mapping
+                    .function_arn()
+                    .map_or(false, |arn| functions.contains(arn))
+            })
+            .collect();
+        progress.inc(1);
+
+        Ok(())
+    }
+
+    pub(crate) fn functions_of(&self, vpc_id: &str) -> Vec<&lambda::types::FunctionConfiguration> {
+        self.functions
+            .iter()
+            .filter(|function| function_vpc(function) == Some(vpc_id))
+            .collect()
+    }
+
+    /// Functions running in this subnet
+    pub(crate) fn subnet_functions(
+        &self,
+        subnet_id: &str,
+    ) -> Vec<&lambda::types::FunctionConfiguration> {
+        self.functions
+            .iter()
+            .filter(|function| function_subnets(function).contains(&subnet_id))
+            .collect()
+    }
+
+    /// Functions using this security group
+    pub(crate) fn security_group_functions(
+        &self,
+        group_id: &str,
+    ) -> Vec<&lambda::types::FunctionConfiguration> {
+        self.functions
+            .iter()
+            .filter(|function| function_security_groups(function).contains(&group_id))
+            .collect()
+    }
+
+    /// Lambda shares a network interface between all the functions with the same subnet and
+    /// security groups, so an interface is explained by every function matching both
+    pub(crate) fn network_interface_functions(
+        &self,
+        subnet_id: Option<&str>,
+        group_ids: &[&str],
+    ) -> Vec<&lambda::types::FunctionConfiguration> {
+        let group_ids = group_ids.iter().copied().collect::<BTreeSet<_>>();
+        self.functions
+            .iter()
+            .filter(|function| {
+                subnet_id.map_or(false, |subnet_id| {
+                    function_subnets(function).contains(&subnet_id)
+                })
+            })
+            .filter(|function| {
+                function_security_groups(function)
+                    .into_iter()
+                    .collect::<BTreeSet<_>>()
+                    == group_ids
+            })
+            .collect()
+    }
+
+    fn event_source_mappings_of(
+        &self,
+        function: &lambda::types::FunctionConfiguration,
+    ) -> Vec<&lambda::types::EventSourceMappingConfiguration> {
+        let function_arn = function.function_arn();
+        self.event_source_mappings
+            .iter()
+            .filter(|mapping| mapping.function_arn() == function_arn)
+            .collect()
+    }
+
+    fn function_tree(
+        &self,
+        function: &lambda::types::FunctionConfiguration,
+    ) -> ptree::item::StringItem {
+        let mut tree = ptree::TreeBuilder::new(function_title(function));
+        let tree = &mut tree;
+        add_function_details(tree, function);
+        if let Some(vpc_id) = function_vpc(function) {
+            tree.add_empty_child(format!("VPC: {vpc_id}"));
+            add_list(tree, "Subnets", &function_subnets(function));
+            add_list(tree, "Security Groups", &function_security_groups(function));
+        }
+        let mappings = self.event_source_mappings_of(function);
+        if !mappings.is_empty() {
+            tree.begin_child(String::from("Event Sources"));
+            for mapping in mappings {
+                let source = mapping.event_source_arn().unwrap_or("no source");
+                let state = mapping.state().unwrap_or("no state");
+                tree.add_empty_child(format!("{source} [{state}]"));
+            }
+            tree.end_child();
+        }
+        tree.build()
+    }
+
+    fn function_json(&self, function: &lambda::types::FunctionConfiguration) -> serde_json::Value {
+        let layers = function
+            .layers()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|layer| layer.arn())
+            .collect::<Vec<_>>();
+        let event_sources = self
+            .event_source_mappings_of(function)
+            .into_iter()
+            .map(|mapping| {
+                serde_json::json!({
+                    "id": mapping.uuid(),
+                    "source": mapping.event_source_arn(),
+                    "state": mapping.state(),
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "id": function.id(),
+            "name": Show::description(&function),
+            "runtime": function.runtime().map(|runtime| runtime.as_str()),
+            "memory_size": function.memory_size(),
+            "last_modified": function.last_modified(),
+            "layers": layers,
+            "vpc_id": function_vpc(function),
+            "subnets": function_subnets(function),
+            "security_groups": function_security_groups(function),
+            "event_sources": event_sources,
+        })
+    }
+}
+
+impl Render for LambdaResources {
+    fn trees(&self) -> Vec<ptree::item::StringItem> {
+        self.functions
+            .iter()
+            .map(|function| self.function_tree(function))
+            .collect()
+    }
+
+    fn json(&self) -> serde_json::Value {
+        let functions = self
+            .functions
+            .iter()
+            .map(|function| self.function_json(function))
+            .collect::<Vec<_>>();
+        serde_json::json!({ "functions": functions })
+    }
+}
+
+fn function_title(function: &lambda::types::FunctionConfiguration) -> String {
+    let runtime = function.runtime().map_or_else(
+        || {
+            function
+                .package_type()
+                .map_or("unknown runtime", |package_type| package_type.as_str())
+        },
+        |runtime| runtime.as_str(),
+    );
+    format!("{} ({runtime})", function.id())
+}
+
+pub(crate) fn add_function_details(
+    tree: &mut ptree::TreeBuilder,
+    function: &lambda::types::FunctionConfiguration,
+) {
+    if let Some(memory_size) = function.memory_size() {
+        tree.add_empty_child(format!("Memory: {memory_size} MB"));
+    }
+    if let Some(last_modified) = function.last_modified() {
+        tree.add_empty_child(format!("Last Modified: {last_modified}"));
+    }
+    let layers = function
+        .layers()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|layer| layer.arn())
+        .collect::<Vec<_>>();
+    if !layers.is_empty() {
+        tree.begin_child(String::from("Layers"));
+        for layer in layers {
+            tree.add_empty_child(layer.to_string());
+        }
+        tree.end_child();
+    }
+}
+
+/// Functions that are not attached to a VPC may still come with an empty VPC configuration
+fn function_vpc(function: &lambda::types::FunctionConfiguration) -> Option<&str> {
+    function
+        .vpc_config()
+        .and_then(|config| config.vpc_id())
+        .filter(|vpc_id| !vpc_id.is_empty())
+}
+
+fn function_subnets(function: &lambda::types::FunctionConfiguration) -> Vec<&str> {
+    function
+        .vpc_config()
+        .and_then(|config| config.subnet_ids())
+        .unwrap_or_default()
+        .iter()
+        .map(String::as_str)
+        .collect()
+}
+
+fn function_security_groups(function: &lambda::types::FunctionConfiguration) -> Vec<&str> {
+    function
+        .vpc_config()
+        .and_then(|config| config.security_group_ids())
+        .unwrap_or_default()
+        .iter()
+        .map(String::as_str)
+        .collect()
+}
@@ -0,0 +1,596 @@
+use std::collections::BTreeSet;
+
+use aws_sdk_rds as rds;
+use tokio_stream::StreamExt;
+
+use crate::aws::{add_detail, has_tags};
+use crate::output::Render;
+use crate::Show;
+
+#[derive(Debug)]
+pub(crate) struct RdsResources {
+    client: rds::Client,
+    tags: Vec<(String, String)>,
+    clusters: Vec<rds::types::DbCluster>,
+    instances: Vec<rds::types::DbInstance>,
+    subnet_groups: Vec<rds::types::DbSubnetGroup>,
+    parameter_groups: Vec<rds::types::DbParameterGroup>,
+    cluster_parameter_groups: Vec<rds::types::DbClusterParameterGroup>,
+    snapshots: Vec<rds::types::DbSnapshot>,
+    cluster_snapshots: Vec<rds::types::DbClusterSnapshot>,
+}
+
+impl RdsResources {
+    pub(crate) fn new(config: &aws_types::SdkConfig, tags: &[(String, String)]) -> Self {
+        let client = rds::Client::new(config);
+        let tags = tags.to_vec();
+        Self {
+            client,
+            tags,
+            clusters: vec![],
+            instances: vec![],
+            subnet_groups: vec![],
+            parameter_groups: vec![],
+            cluster_parameter_groups: vec![],
+            snapshots: vec![],
+            cluster_snapshots: vec![],
+        }
+    }
+
+    /// Collect the DB clusters and instances of the given VPCs (or all of them)
+    pub(crate) async fn collect_databases(
+        &mut self,
+        vpcs: &[String],
+        progress: &indicatif::ProgressBar,
+    ) -> Result<(), rds::Error> {
+        progress.inc_length(2);
+        progress.set_message("Databases");
+
+        let (clusters, instances) =
+            tokio::try_join!(self.collect_clusters(), self.collect_instances())?;
+        progress.inc(2);
+
+        let vpcs = vpcs.iter().map(String::as_str).collect::<BTreeSet<_>>();
+        self.instances = instances
+            .into_iter()
+            .filter(|instance| is_in(&vpcs, instance_vpc(instance)))
+            .filter(|instance| has_tags(&self.tags, instance.tag_list()))
+            .collect();
+        // Clusters do not know their VPC, only their instances do
+        let cluster_ids = self
+            .instances
+            .iter()
+            .filter_map(|instance| instance.db_cluster_identifier())
+            .collect::<BTreeSet<_>>();
+        self.clusters = clusters
+            .into_iter()
+            .filter(|cluster| {
+                (vpcs.is_empty() && has_tags(&self.tags, cluster.tag_list()))
+                    || cluster
+                        .db_cluster_identifier()
+                        .map_or(false, |id| cluster_ids.contains(id))
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    /// Collect the databases and also their subnet groups, parameter groups and snapshots
+    pub(crate) async fn collect(
+        &mut self,
+        vpcs: &[String],
+        progress: &indicatif::ProgressBar,
+    ) -> Result<(), rds::Error> {
+        self.collect_databases(vpcs, progress).await?;
+
+        progress.inc_length(5);
+        progress.set_message("Groups and snapshots");
+        let (
+            subnet_groups,
+            parameter_groups,
+            cluster_parameter_groups,
+            snapshots,
+            cluster_snapshots,
+        ) = tokio::try_join!(
+            self.collect_subnet_groups(),
+            self.collect_parameter_groups(),
+            self.collect_cluster_parameter_groups(),
+            self.collect_snapshots(),
+            self.collect_cluster_snapshots(),
+        )?;
+        progress.inc(5);
+
+        let vpcs = vpcs.iter().map(String::as_str).collect::<BTreeSet<_>>();
+        let filtered = !vpcs.is_empty() || !self.tags.is_empty();
+        self.subnet_groups = subnet_groups
+            .into_iter()
+            .filter(|subnet_group| {
+                !filtered
+                    || self.instances.iter().any(|instance| {
+                        instance
+                            .db_subnet_group()
+                            .and_then(|group| group.db_subnet_group_name())
+                            == subnet_group.db_subnet_group_name()
+                    })
+            })
+            .collect();
+        self.parameter_groups = parameter_groups
+            .into_iter()
+            .filter(|parameter_group| {
+                !filtered
+                    || self.instances.iter().any(|instance| {
+                        instance
+                            .db_parameter_groups()
+                            .unwrap_or_default()
+                            .iter()
+                            .any(|group| {
+                                group.db_parameter_group_name()
+                                    == parameter_group.db_parameter_group_name()
+                            })
+                    })
+            })
+            .collect();
+        self.cluster_parameter_groups = cluster_parameter_groups
+            .into_iter()
+            .filter(|parameter_group| {
+                !filtered
+                    || self.clusters.iter().any(|cluster| {
+                        cluster.db_cluster_parameter_group()
+                            == parameter_group.db_cluster_parameter_group_name()
+                    })
+            })
+            .collect();
+        self.snapshots = snapshots
+            .into_iter()
+            .filter(|snapshot| is_in(&vpcs, snapshot.vpc_id()))
+            .filter(|snapshot| has_tags(&self.tags, snapshot.tag_list()))
+            .collect();
+        self.cluster_snapshots = cluster_snapshots
+            .into_iter()
+            .filter(|snapshot| is_in(&vpcs, snapshot.vpc_id()))
+            .filter(|snapshot| has_tags(&self.tags, snapshot.tag_list()))
+            .collect();
+
+        Ok(())
+    }
+
+    async fn collect_clusters(&self) -> Result<Vec<rds::types::DbCluster>, rds::Error> {
+        let clusters = self
+            .client
+            .describe_db_clusters()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(clusters)
+    }
+
+    async fn collect_instances(&self) -> Result<Vec<rds::types::DbInstance>, rds::Error> {
+        let instances = self
+            .client
+            .describe_db_instances()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(instances)
+    }
+
+    async fn collect_subnet_groups(&self) -> Result<Vec<rds::types::DbSubnetGroup>, rds::Error> {
+        let subnet_groups = self
+            .client
+            .describe_db_subnet_groups()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(subnet_groups)
+    }
+
+    async fn collect_parameter_groups(
+        &self,
+    ) -> Result<Vec<rds::types::DbParameterGroup>, rds::Error> {
+        let parameter_groups = self
+            .client
+            .describe_db_parameter_groups()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(parameter_groups)
+    }
+
+    async fn collect_cluster_parameter_groups(
+        &self,
+    ) -> Result<Vec<rds::types::DbClusterParameterGroup>, rds::Error> {
+        let parameter_groups = self
+            .client
+            .describe_db_cluster_parameter_groups()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(parameter_groups)
+    }
+
+    async fn collect_snapshots(&self) -> Result<Vec<rds::types::DbSnapshot>, rds::Error> {
+        let snapshots = self
+            .client
+            .describe_db_snapshots()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(snapshots)
+    }
+
+    async fn collect_cluster_snapshots(
+        &self,
+    ) -> Result<Vec<rds::types::DbClusterSnapshot>, rds::Error> {
+        let snapshots = self
+            .client
+            .describe_db_cluster_snapshots()
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<_, _>>()
+            .await?;
+
+        Ok(snapshots)
+    }
+
+    pub(crate) fn instances_of(&self, vpc_id: &str) -> Vec<&rds::types::DbInstance> {
+        let vpc_id = Some(vpc_id);
+        self.instances
+            .iter()
+            .filter(|instance| instance_vpc(instance) == vpc_id)
+            .collect()
+    }
+
+    fn cluster_instances(&self, cluster: &rds::types::DbCluster) -> Vec<&rds::types::DbInstance> {
+        let cluster_id = cluster.db_cluster_identifier();
+        self.instances
+            .iter()
+            .filter(|instance| instance.db_cluster_identifier() == cluster_id)
+            .collect()
+    }
+
+    fn standalone_instances(&self) -> Vec<&rds::types::DbInstance> {
+        self.instances
+            .iter()
+            .filter(|instance| instance.db_cluster_identifier().is_none())
+            .collect()
+    }
+
+    fn cluster_tree(&self, cluster: &rds::types::DbCluster) -> ptree::item::StringItem {
+        let mut tree = ptree::TreeBuilder::new(cluster_title(cluster));
+        let tree = &mut tree;
+        add_detail(tree, "Endpoint", cluster.endpoint());
+        add_detail(tree, "Reader Endpoint", cluster.reader_endpoint());
+        add_detail(tree, "Subnet Group", cluster.db_subnet_group());
+        add_detail(
+            tree,
+            "Parameter Group",
+            cluster.db_cluster_parameter_group(),
+        );
+        let writers = cluster
+            .db_cluster_members()
+            .unwrap_or_default()
+            .iter()
+            .filter(|member| member.is_cluster_writer())
+            .filter_map(|member| member.db_instance_identifier())
+            .collect::<BTreeSet<_>>();
+        let instances = self.cluster_instances(cluster);
+        if !instances.is_empty() {
+            tree.begin_child(String::from("Instances"));
+            for instance in instances {
+                let role = if instance
+                    .db_instance_identifier()
+                    .map_or(false, |id| writers.contains(id))
+                {
+                    "writer"
+                } else {
+                    "reader"
+                };
+                tree.begin_child(format!("{} ({role})", instance_title(instance)));
+                add_instance_details(tree, instance);
+                tree.end_child();
+            }
+            tree.end_child();
+        }
+        tree.build()
+    }
+
+    fn instance_tree(&self, instance: &rds::types::DbInstance) -> ptree::item::StringItem {
+        let mut tree = ptree::TreeBuilder::new(instance_title(instance));
+        add_instance_details(&mut tree, instance);
+        tree.build()
+    }
+
+    fn subnet_group_tree(&self) -> Option<ptree::item::StringItem> {
+        if self.subnet_groups.is_empty() {
+            return None;
+        }
+        let mut tree = ptree::TreeBuilder::new(String::from("DB Subnet Groups"));
+        for subnet_group in self.subnet_groups.iter() {
+            tree.begin_child(subnet_group.id_and_name());
+            add_detail(&mut tree, "VPC", subnet_group.vpc_id());
+            for subnet in subnet_group.subnets().unwrap_or_default() {
+                let subnet_id = subnet.subnet_identifier().unwrap_or("no id");
+                let zone = subnet
+                    .subnet_availability_zone()
+                    .and_then(|zone| zone.name())
+                    .unwrap_or("unknown zone");
+                tree.add_empty_child(format!("{subnet_id} [{zone}]"));
+            }
+            tree.end_child();
+        }
+        Some(tree.build())
+    }
+
+    fn parameter_group_tree(&self) -> Option<ptree::item::StringItem> {
+        if self.parameter_groups.is_empty() && self.cluster_parameter_groups.is_empty() {
+            return None;
+        }
+        let mut tree = ptree::TreeBuilder::new(String::from("DB Parameter Groups"));
+        for parameter_group in self.parameter_groups.iter() {
+            let family = parameter_group
+                .db_parameter_group_family()
+                .unwrap_or("no family");
+            tree.add_empty_child(format!("{} [{family}]", parameter_group.id_and_name()));
+        }
+        for parameter_group in self.cluster_parameter_groups.iter() {
+            let family = parameter_group
+                .db_parameter_group_family()
+                .unwrap_or("no family");
+            tree.add_empty_child(format!(
+                "{} [{family}, cluster]",
+                parameter_group.id_and_name()
+            ));
+        }
+        Some(tree.build())
+    }
+
+    fn snapshot_tree(&self) -> Option<ptree::item::StringItem> {
+        if self.snapshots.is_empty() && self.cluster_snapshots.is_empty() {
+            return None;
+        }
+        let mut tree = ptree::TreeBuilder::new(String::from("DB Snapshots"));
+        for snapshot in self.snapshots.iter() {
+            tree.add_empty_child(snapshot_title(
+                snapshot.id_and_name(),
+                snapshot.db_instance_identifier(),
+                snapshot.snapshot_type(),
+                snapshot.status(),
+                snapshot.snapshot_create_time(),
+            ));
+        }
+        for snapshot in self.cluster_snapshots.iter() {
+            tree.add_empty_child(snapshot_title(
+                snapshot.id_and_name(),
+                snapshot.db_cluster_identifier(),
+                snapshot.snapshot_type(),
+                snapshot.status(),
+                snapshot.snapshot_create_time(),
+            ));
+        }
+        Some(tree.build())
+    }
+}
+
+impl Render for RdsResources {
+    fn trees(&self) -> Vec<ptree::item::StringItem> {
+        let clusters = self
+            .clusters
+            .iter()
+            .map(|cluster| self.cluster_tree(cluster));
+        let instances = self
+            .standalone_instances()
+            .into_iter()
+            .map(|instance| self.instance_tree(instance));
+        clusters
+            .chain(instances)
+            .chain(self.subnet_group_tree())
+            .chain(self.parameter_group_tree())
+            .chain(self.snapshot_tree())
+            .collect()
+    }
+
+    fn json(&self) -> serde_json::Value {
+        let clusters = self
+            .clusters
+            .iter()
+            .map(|cluster| {
+                let instances = self
+                    .cluster_instances(cluster)
+                    .into_iter()
+                    .map(|instance| instance.id())
+                    .collect::<Vec<_>>();
+                serde_json::json!({
+                    "id": cluster.id(),
+                    "name": cluster.name(),
+                    "engine": cluster.engine(),
+                    "engine_version": cluster.engine_version(),
+                    "status": cluster.status(),
+                    "endpoint": cluster.endpoint(),
+                    "reader_endpoint": cluster.reader_endpoint(),
+                    "subnet_group": cluster.db_subnet_group(),
+                    "parameter_group": cluster.db_cluster_parameter_group(),
+                    "instances": instances,
+                })
+            })
+            .collect::<Vec<_>>();
+        let instances = self.instances.iter().map(instance_json).collect::<Vec<_>>();
+        let subnet_groups = self
+            .subnet_groups
+            .iter()
+            .map(|subnet_group| {
+                let subnets = subnet_group
+                    .subnets()
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|subnet| subnet.subnet_identifier())
+                    .collect::<Vec<_>>();
+                serde_json::json!({
+                    "id": subnet_group.id(),
+                    "name": subnet_group.db_subnet_group_name(),
+                    "description": subnet_group.description(),
+                    "vpc_id": subnet_group.vpc_id(),
+                    "subnets": subnets,
+                })
+            })
+            .collect::<Vec<_>>();
+        let parameter_groups = self
+            .parameter_groups
+            .iter()
+            .map(|resource| resource.json())
+            .chain(
+                self.cluster_parameter_groups
+                    .iter()
+                    .map(|resource| resource.json()),
+            )
+            .collect::<Vec<_>>();
+        let snapshots = self
+            .snapshots
+            .iter()
+            .map(|resource| resource.json())
+            .chain(
+                self.cluster_snapshots
+                    .iter()
+                    .map(|resource| resource.json()),
+            )
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "clusters": clusters,
+            "instances": instances,
+            "subnet_groups": subnet_groups,
+            "parameter_groups": parameter_groups,
+            "snapshots": snapshots,
+        })
+    }
+}
+
+/// The subnets an instance may live in: those of its subnet group in its availability zone
+pub(crate) fn instance_subnet_candidates(instance: &rds::types::DbInstance) -> Vec<&str> {
+    let zone = instance.availability_zone();
+    instance
+        .db_subnet_group()
+        .and_then(|group| group.subnets())
+        .unwrap_or_default()
+        .iter()
+        .filter(|subnet| {
+            zone.is_none()
+                || subnet
+                    .subnet_availability_zone()
+                    .and_then(|zone| zone.name())
+                    == zone
+        })
+        .filter_map(|subnet| subnet.subnet_identifier())
+        .collect()
+}
+
+pub(crate) fn instance_title(instance: &rds::types::DbInstance) -> String {
+    let engine = instance.engine().unwrap_or("no engine");
+    let class = instance.db_instance_class().unwrap_or("no class");
+    let status = instance.db_instance_status().unwrap_or("no status");
+    format!("{} ({engine}, {class}) [{status}]", instance.id())
+}
+
+pub(crate) fn add_instance_details(
+    tree: &mut ptree::TreeBuilder,
+    instance: &rds::types::DbInstance,
+) {
+    let endpoint = instance.endpoint().map(|endpoint| {
+        let address = endpoint.address().unwrap_or("no address");
+        format!("{address}:{}", endpoint.port())
+    });
+    add_detail(tree, "Endpoint", endpoint.as_deref());
+    add_detail(tree, "Engine Version", instance.engine_version());
+    add_detail(tree, "Availability Zone", instance.availability_zone());
+    if instance.multi_az() {
+        tree.add_empty_child(String::from("Multi-AZ"));
+    }
+    add_detail(
+        tree,
+        "Subnet Group",
+        instance
+            .db_subnet_group()
+            .and_then(|group| group.db_subnet_group_name()),
+    );
+    for parameter_group in instance.db_parameter_groups().unwrap_or_default() {
+        add_detail(
+            tree,
+            "Parameter Group",
+            parameter_group.db_parameter_group_name(),
+        );
+    }
+    for security_group in instance.vpc_security_groups().unwrap_or_default() {
+        add_detail(
+            tree,
+            "Security Group",
+            security_group.vpc_security_group_id(),
+        );
+    }
+}
+
+fn instance_json(instance: &rds::types::DbInstance) -> serde_json::Value {
+    serde_json::json!({
+        "id": instance.id(),
+        "name": instance.name(),
+        "cluster": instance.db_cluster_identifier(),
+        "engine": instance.engine(),
+        "engine_version": instance.engine_version(),
+        "class": instance.db_instance_class(),
+        "status": instance.db_instance_status(),
+        "availability_zone": instance.availability_zone(),
+        "multi_az": instance.multi_az(),
+        "vpc_id": instance_vpc(instance),
+        "subnet_group": instance
+            .db_subnet_group()
+            .and_then(|group| group.db_subnet_group_name()),
+        "endpoint": instance.endpoint().and_then(|endpoint| endpoint.address()),
+    })
+}
+
+fn cluster_title(cluster: &rds::types::DbCluster) -> String {
+    let engine = cluster.engine().unwrap_or("no engine");
+    let version = cluster.engine_version().unwrap_or("no version");
+    let status = cluster.status().unwrap_or("no status");
+    format!("{} ({engine} {version}) [{status}]", cluster.id())
+}
+
+fn snapshot_title(
+    id: String,
+    source: Option<&str>,
+    r#type: Option<&str>,
+    status: Option<&str>,
+    created: Option<&rds::primitives::DateTime>,
+) -> String {
+    let source = source.unwrap_or("no source");
+    let r#type = r#type.unwrap_or("unknown");
+    let status = status.unwrap_or("no status");
+    let created = created
+        .and_then(|created| created.fmt(rds::primitives::DateTimeFormat::DateTime).ok())
+        .unwrap_or_else(|| String::from("no date"));
+    format!("{id} of {source} ({type}, {created}) [{status}]")
+}
+
+fn instance_vpc(instance: &rds::types::DbInstance) -> Option<&str> {
+    instance.db_subnet_group().and_then(|group| group.vpc_id())
+}
+
+fn is_in(vpcs: &BTreeSet<&str>, vpc_id: Option<&str>) -> bool {
+    vpcs.is_empty() || vpc_id.map_or(false, |vpc_id| vpcs.contains(vpc_id))
+}
@@ -0,0 +1,57 @@
+use std::fmt::Write;
+
+pub(crate) fn graph(regions: Vec<(String, Vec<String>)>) -> String {
+    let mut graph = String::from("digraph aware {\n    rankdir=LR;\n    node [fontsize=10];\n");
+    for (region, statements) in regions {
+        let _ = writeln!(
+            graph,
+            "    subgraph {} {{",
+            quote(format!("cluster_{region}"))
+        );
+        let _ = writeln!(graph, "        label={};", quote(&region));
+        for statement in statements {
+            let _ = writeln!(graph, "        {statement}");
+        }
+        let _ = writeln!(graph, "    }}");
+    }
+    graph.push_str("}\n");
+    graph
+}
+
+pub(crate) fn cluster(
+    id: impl AsRef<str>,
+    label: impl AsRef<str>,
+    statements: Vec<String>,
+) -> Vec<String> {
+    let mut cluster = vec![
+        format!("subgraph {} {{", quote(format!("cluster_{}", id.as_ref()))),
+        format!("    label={};", quote(label)),
+    ];
+    cluster.extend(
+        statements
+            .into_iter()
+            .map(|statement| format!("    {statement}")),
+    );
+    cluster.push(String::from("}"));
+    cluster
+}
+
+pub(crate) fn node(id: impl AsRef<str>, label: impl AsRef<str>, shape: &str) -> String {
+    format!("{} [label={}, shape={shape}];", quote(id), quote(label))
+}
+
+pub(crate) fn edge(from: impl AsRef<str>, to: impl AsRef<str>, label: Option<&str>) -> String {
+    match label {
+        Some(label) => format!("{} -> {} [label={}];", quote(from), quote(to), quote(label)),
+        None => format!("{} -> {};", quote(from), quote(to)),
+    }
+}
+
+fn quote(text: impl AsRef<str>) -> String {
+    let text = text
+        .as_ref()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{text}\"")
+}
@@ -29,14 +29,21 @@
 // aws ec2 describe-network-interfaces --filters 'Name=vpc-id,Values='$vpc | grep NetworkInterfaceId
 
 use std::env;
+use std::future::Future;
+use std::num::NonZeroUsize;
 
 use aws_config::meta::region::RegionProviderChain;
 use aws_types::region::Region;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use futures::stream::{self, StreamExt};
 
+use output::{Output, Render, Report};
 use show::Show;
 
 mod aws;
+mod dot;
+mod mermaid;
+mod output;
 mod show;
 
 #[derive(Debug, Parser)]
@@ -48,6 +55,29 @@ struct Aware {
         global = true
     )]
     region: Vec<String>,
+    #[arg(
+        help = "Also explore regions the account has not opted in to",
+        long,
+        global = true
+    )]
+    include_not_opted_in: bool,
+    #[arg(
+        help = "Output format",
+        long,
+        short,
+        global = true,
+        value_enum,
+        default_value_t = Output::Tree
+    )]
+    output: Output,
+    #[arg(
+        help = "Explore up to this many regions at the same time (calls made once per resource \
+                run at most 4 at a time per service within a region)",
+        long,
+        global = true,
+        default_value = "8"
+    )]
+    concurrency: NonZeroUsize,
     #[command(subcommand)]
     service: AwsService,
 }
@@ -62,16 +92,104 @@ pub(crate) enum AwsService {
         vpc: Vec<String>,
         #[arg(help = "Filter by tag", long, value_parser = parse_tag)]
         tag: Vec<(String, String)>,
+        #[arg(
+            help = "Show instance state, type, IP addresses and availability zone",
+            long
+        )]
+        details: bool,
+        #[arg(help = "Show security group ingress and egress rules", long)]
+        rules: bool,
+        #[arg(
+            help = "How to arrange resources inside a VPC",
+            long,
+            value_enum,
+            default_value_t = aws::ec2::Layout::Type
+        )]
+        layout: aws::ec2::Layout,
     },
-    #[command(name = "cf", about = "Explore CloudFormation resources")]
+    #[command(name = "elb", about = "Explore Elastic Load Balancing resources")]
+    Elb {
+        #[arg(help = "Filter by VPC", long, short)]
+        vpc: Vec<String>,
+        #[arg(help = "Filter by tag", long, value_parser = parse_tag)]
+        tag: Vec<(String, String)>,
+    },
+    #[command(name = "rds", about = "Explore RDS and Aurora resources")]
+    Rds {
+        #[arg(help = "Filter by VPC", long, short)]
+        vpc: Vec<String>,
+        #[arg(help = "Filter by tag", long, value_parser = parse_tag)]
+        tag: Vec<(String, String)>,
+    },
+    #[command(name = "eks", about = "Explore EKS clusters")]
+    Eks {
+        #[arg(help = "Filter by given cluster name", long)]
+        cluster: Vec<String>,
+        #[arg(help = "Filter by VPC", long, short)]
+        vpc: Vec<String>,
+    },
+    #[command(name = "lambda", about = "Explore Lambda functions")]
+    Lambda {
+        #[arg(help = "Filter by VPC", long, short)]
+        vpc: Vec<String>,
+    },
+    #[command(
+        name = "cf",
+        about = "Explore CloudFormation resources",
+        args_conflicts_with_subcommands = true
+    )]
     CloudFormation {
+        #[command(subcommand)]
+        command: Option<CfCommand>,
         #[arg(help = "Filter by given stack name", long)]
         stack: Vec<String>,
         #[arg(help = "Filter by given stack status", long)]
         status: Vec<aws::cf::StackStatus>,
+        #[arg(help = "Show only root stacks, leaving out nested stacks", long)]
+        root_only: bool,
     },
 }
 
+impl AwsService {
+    /// Graphs are only drawn for the VPC topology and for CloudFormation stacks and exports
+    fn supports(&self, output: Output) -> bool {
+        match (self, output) {
+            (_, Output::Tree | Output::Json) => true,
+            (Self::Ec2 { list_tags, .. }, _) => !list_tags,
+            (
+                Self::CloudFormation {
+                    command: Some(CfCommand::Exports),
+                    ..
+                },
+                _,
+            ) => true,
+            (Self::CloudFormation { command: None, .. }, Output::Mermaid) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum CfCommand {
+    #[command(
+        name = "events",
+        about = "Show the latest stack events and the failure that caused a rollback"
+    )]
+    Events {
+        #[arg(help = "Filter by given stack name", long)]
+        stack: Vec<String>,
+        #[arg(help = "Filter by given stack status", long)]
+        status: Vec<aws::cf::StackStatus>,
+        #[arg(help = "Show every event instead of the latest operation only", long)]
+        all: bool,
+    },
+    #[command(
+        name = "exports",
+        about = "Show which stacks import the exports of which stacks and a safe delete order"
+    )]
+    Exports,
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Turn logging off by default
@@ -83,102 +201,293 @@ async fn main() -> anyhow::Result<()> {
 
     let aware = Aware::parse();
 
+    if !aware.service.supports(aware.output) {
+        let output = aware
+            .output
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        Aware::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                format!("--output {output} is not supported by this command"),
+            )
+            .exit();
+    }
+
     let regions = if aware.region.is_empty() {
-        aws::get_all_regions().await?
+        aws::get_all_regions(aware.include_not_opted_in).await?
     } else {
         aware.region
     };
 
+    let concurrency = aware.concurrency.get();
+
+    let output = aware.output;
     match aware.service {
         AwsService::Ec2 {
             list_tags,
             vpc,
             tag,
-        } => collect_ec2(regions, list_tags, vpc, tag).await,
-        AwsService::CloudFormation { stack, status } => collect_cf(regions, stack, status).await,
+            details,
+            rules,
+            layout,
+        } => {
+            let options = aws::ec2::Options {
+                details,
+                rules,
+                layout,
+            };
+            explore(regions, output, concurrency, STYLE, |config, progress| {
+                collect_ec2(config, progress, list_tags, &vpc, &tag, options)
+            })
+            .await
+        }
+        AwsService::Elb { vpc, tag } => {
+            explore(regions, output, concurrency, STYLE, |config, progress| {
+                collect_elb(config, progress, &vpc, &tag)
+            })
+            .await
+        }
+        AwsService::Rds { vpc, tag } => {
+            explore(regions, output, concurrency, STYLE, |config, progress| {
+                collect_rds(config, progress, &vpc, &tag)
+            })
+            .await
+        }
+        AwsService::Eks { cluster, vpc } => {
+            explore(regions, output, concurrency, STYLE, |config, progress| {
+                collect_eks(config, progress, &cluster, &vpc)
+            })
+            .await
+        }
+        AwsService::Lambda { vpc } => {
+            explore(regions, output, concurrency, STYLE, |config, progress| {
+                collect_lambda(config, progress, &vpc)
+            })
+            .await
+        }
+        AwsService::CloudFormation {
+            command: Some(CfCommand::Events { stack, status, all }),
+            ..
+        } => {
+            let statuses = aws::cf::adjust_stack_statuses(status);
+            explore(
+                regions,
+                output,
+                concurrency,
+                CF_STYLE,
+                |config, progress| collect_cf_events(config, progress, &stack, &statuses, all),
+            )
+            .await
+        }
+        AwsService::CloudFormation {
+            command: Some(CfCommand::Exports),
+            ..
+        } => explore(regions, output, concurrency, CF_STYLE, collect_cf_exports).await,
+        AwsService::CloudFormation {
+            command: None,
+            stack,
+            status,
+            root_only,
+        } => {
+            let statuses = aws::cf::adjust_stack_statuses(status);
+            explore(
+                regions,
+                output,
+                concurrency,
+                CF_STYLE,
+                |config, progress| collect_cf(config, progress, &stack, &statuses, root_only),
+            )
+            .await
+        }
     }
 }
 
-async fn collect_ec2(
+const STYLE: &str = "[{prefix}] {pos}/{len} | {msg:24} {wide_bar} [{elapsed}/{duration} ETA {eta}]";
+const CF_STYLE: &str =
+    "[{pos:>3}/{len:>3} {prefix}] {msg:24!} {wide_bar} [{elapsed}/{duration} ETA {eta}]";
+
+/// Collect the resources of every region with `collect`, one progress bar per region
+async fn explore<R, F>(
     regions: Vec<String>,
-    list_tags: bool,
-    vpc: Vec<String>,
-    tags: Vec<(String, String)>,
-) -> anyhow::Result<()> {
-    let regioned_clients = regions
-        .into_iter()
-        .map(Region::new)
-        .map(RegionProviderChain::first_try);
-
-    for region in regioned_clients {
-        let shared_config = aws_config::from_env().region(region).load().await;
-
-        let style = indicatif::ProgressStyle::default_bar().template(
-            "[{prefix}] {pos}/{len} | {msg:24} {wide_bar} [{elapsed}/{duration} ETA {eta}]",
-        )?;
-        let progress = indicatif::ProgressBar::new(1).with_style(style);
-        progress.set_prefix(shared_config.region().id_and_name());
-        let mut ec2 = aws::Ec2Resources::new(&shared_config, &tags);
-
-        if list_tags {
-            progress.set_message("Collecting Tags");
-            ec2.collect_tags(&progress).await?;
-            progress.inc(1);
-        } else {
-            progress.set_message("Collecting VPCs");
-            ec2.collect_vpcs(&vpc).await?;
-            progress.inc(1);
-            ec2.collect(&progress).await?;
+    output: Output,
+    concurrency: usize,
+    template: &str,
+    collect: impl Fn(aws_types::SdkConfig, indicatif::ProgressBar) -> F,
+) -> anyhow::Result<()>
+where
+    R: Render,
+    F: Future<Output = anyhow::Result<R>>,
+{
+    let style = indicatif::ProgressStyle::default_bar().template(template)?;
+    let progress = indicatif::MultiProgress::new();
+    let mut report = Report::new(output);
+
+    // `buffered` runs up to `concurrency` regions at once but yields them in the original order
+    let mut regions = stream::iter(regions)
+        .map(|region| {
+            let progress = progress.add(indicatif::ProgressBar::new(0).with_style(style.clone()));
+            let collect = &collect;
+            async move {
+                let provider = RegionProviderChain::first_try(Region::new(region.clone()));
+                let shared_config = aws_config::from_env().region(provider).load().await;
+                let result = collect(shared_config, progress.clone()).await;
+                match result {
+                    Ok(_) => progress.finish(),
+                    Err(_) => progress.abandon_with_message("Failed"),
+                }
+                (region, result)
+            }
+        })
+        .buffered(concurrency);
+
+    while let Some((region, result)) = regions.next().await {
+        match result {
+            Ok(resources) => progress.suspend(|| report.add(region, &resources))?,
+            Err(error) => report.skip(region, error),
         }
+    }
 
-        progress.finish();
+    report.finish()
+}
 
-        ec2.trees().for_each(|tree| {
-            println!();
-            ptree::print_tree(&tree).expect("Failed to print tree");
-        });
+async fn collect_ec2(
+    shared_config: aws_types::SdkConfig,
+    progress: indicatif::ProgressBar,
+    list_tags: bool,
+    vpc: &[String],
+    tags: &[(String, String)],
+    options: aws::ec2::Options,
+) -> anyhow::Result<aws::Ec2Resources> {
+    progress.set_prefix(shared_config.region().id_and_name());
+    let mut ec2 = aws::Ec2Resources::new(&shared_config, tags, options);
+    progress.inc_length(1);
+
+    if list_tags {
+        progress.set_message("Collecting Tags");
+        ec2.collect_tags(&progress).await?;
+        progress.inc(1);
+    } else {
+        progress.set_message("Collecting VPCs");
+        ec2.collect_vpcs(vpc).await?;
+        progress.inc(1);
+        ec2.collect(&progress).await?;
+        ec2.collect_attached(&progress).await?;
     }
 
-    Ok(())
+    Ok(ec2)
+}
+
+async fn collect_elb(
+    shared_config: aws_types::SdkConfig,
+    progress: indicatif::ProgressBar,
+    vpc: &[String],
+    tags: &[(String, String)],
+) -> anyhow::Result<aws::ElbResources> {
+    progress.set_prefix(shared_config.region().id_and_name());
+    let mut elb = aws::ElbResources::new(&shared_config, tags);
+    elb.collect(vpc, &progress).await?;
+
+    Ok(elb)
+}
+
+async fn collect_rds(
+    shared_config: aws_types::SdkConfig,
+    progress: indicatif::ProgressBar,
+    vpc: &[String],
+    tags: &[(String, String)],
+) -> anyhow::Result<aws::RdsResources> {
+    progress.set_prefix(shared_config.region().id_and_name());
+    let mut rds = aws::RdsResources::new(&shared_config, tags);
+    rds.collect(vpc, &progress).await?;
+
+    Ok(rds)
+}
+
+async fn collect_eks(
+    shared_config: aws_types::SdkConfig,
+    progress: indicatif::ProgressBar,
+    cluster: &[String],
+    vpc: &[String],
+) -> anyhow::Result<aws::EksResources> {
+    progress.set_prefix(shared_config.region().id_and_name());
+    let mut eks = aws::EksResources::new(&shared_config);
+    eks.collect(cluster, vpc, &progress).await?;
+
+    Ok(eks)
+}
+
+async fn collect_lambda(
+    shared_config: aws_types::SdkConfig,
+    progress: indicatif::ProgressBar,
+    vpc: &[String],
+) -> anyhow::Result<aws::LambdaResources> {
+    progress.set_prefix(shared_config.region().id_and_name());
+    let mut lambda = aws::LambdaResources::new(&shared_config);
+    lambda.collect(vpc, &progress).await?;
+
+    Ok(lambda)
 }
 
 async fn collect_cf(
-    regions: Vec<String>,
-    stack: Vec<String>,
-    status: Vec<aws::cf::StackStatus>,
-) -> anyhow::Result<()> {
-    let regioned_clients = regions
-        .into_iter()
-        .map(Region::new)
-        .map(RegionProviderChain::first_try);
-    let statuses = aws::cf::adjust_stack_statuses(status);
-
-    for region in regioned_clients {
-        let shared_config = aws_config::from_env().region(region).load().await;
-        let region = format!("AWS Region {:?}", shared_config.region().id_and_name());
-        // let client = cf::Client::new(&shared_config);
-
-        let style = indicatif::ProgressStyle::default_bar().template(
-            "[{pos:>3}/{len:>3} {prefix}] {msg:24!} {wide_bar} [{elapsed}/{duration} ETA {eta}]",
-        )?;
-        let progress = indicatif::ProgressBar::new(1).with_style(style);
-        progress.set_prefix(region.clone());
-        let mut cf = aws::CfResources::new(&shared_config);
-        progress.set_message("Collecting stacks");
-        cf.collect_stacks(&stack, &statuses).await?;
-        progress.inc(1);
+    shared_config: aws_types::SdkConfig,
+    progress: indicatif::ProgressBar,
+    stack: &[String],
+    statuses: &[aws::cf::StackStatus],
+    root_only: bool,
+) -> anyhow::Result<aws::CfResources> {
+    progress.set_prefix(format!(
+        "AWS Region {:?}",
+        shared_config.region().id_and_name()
+    ));
+    let mut cf = aws::CfResources::new(&shared_config);
+    progress.inc_length(1);
+    progress.set_message("Collecting stacks");
+    cf.collect_stacks(stack, statuses, root_only).await?;
+    progress.inc(1);
 
-        cf.collect_stack_resources(&progress).await?;
+    cf.collect_stack_descriptions(&progress).await?;
 
-        progress.finish();
+    cf.collect_stack_resources(&progress).await?;
 
-        cf.trees().for_each(|tree| {
-            println!();
-            ptree::print_tree(&tree).expect("Failed to print tree");
-        });
-    }
+    Ok(cf)
+}
+
+async fn collect_cf_events(
+    shared_config: aws_types::SdkConfig,
+    progress: indicatif::ProgressBar,
+    stack: &[String],
+    statuses: &[aws::cf::StackStatus],
+    all: bool,
+) -> anyhow::Result<aws::CfEvents> {
+    progress.set_prefix(format!(
+        "AWS Region {:?}",
+        shared_config.region().id_and_name()
+    ));
+    let mut events = aws::CfEvents::new(&shared_config);
+    progress.inc_length(1);
+    progress.set_message("Collecting stacks");
+    events.collect_stacks(stack, statuses).await?;
+    progress.inc(1);
+
+    events.collect_events(all, &progress).await?;
+
+    Ok(events)
+}
+
+async fn collect_cf_exports(
+    shared_config: aws_types::SdkConfig,
+    progress: indicatif::ProgressBar,
+) -> anyhow::Result<aws::CfExports> {
+    progress.set_prefix(format!(
+        "AWS Region {:?}",
+        shared_config.region().id_and_name()
+    ));
+    let mut exports = aws::CfExports::new(&shared_config);
+    exports.collect(&progress).await?;
 
-    Ok(())
+    Ok(exports)
 }
 
 fn parse_tag(text: &str) -> anyhow::Result<(String, String)> {
@@ -0,0 +1,49 @@
+pub(crate) fn flowchart(regions: Vec<(String, Vec<String>)>) -> String {
+    let mut flowchart = String::from("flowchart LR\n");
+    for (region, statements) in regions {
+        for statement in subgraph(&region, &region, statements) {
+            flowchart.push_str("    ");
+            flowchart.push_str(&statement);
+            flowchart.push('\n');
+        }
+    }
+    flowchart
+}
+
+pub(crate) fn subgraph(
+    id: impl AsRef<str>,
+    label: impl AsRef<str>,
+    statements: Vec<String>,
+) -> Vec<String> {
+    let mut subgraph = vec![format!("subgraph {} [{}]", sanitize(id), quote(label))];
+    subgraph.extend(
+        statements
+            .into_iter()
+            .map(|statement| format!("    {statement}")),
+    );
+    subgraph.push(String::from("end"));
+    subgraph
+}
+
+pub(crate) fn node(id: impl AsRef<str>, label: impl AsRef<str>) -> String {
+    format!("{}[{}]", sanitize(id), quote(label))
+}
+
+pub(crate) fn edge(from: impl AsRef<str>, to: impl AsRef<str>, label: Option<&str>) -> String {
+    match label {
+        Some(label) => format!("{} -->|{}| {}", sanitize(from), quote(label), sanitize(to)),
+        None => format!("{} --> {}", sanitize(from), sanitize(to)),
+    }
+}
+
+/// Mermaid ids may only contain alphanumerics and underscores
+fn sanitize(id: impl AsRef<str>) -> String {
+    id.as_ref()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn quote(text: impl AsRef<str>) -> String {
+    format!("\"{}\"", text.as_ref().replace('"', "#quot;"))
+}
@@ -0,0 +1,124 @@
+use clap::ValueEnum;
+
+use crate::{dot, mermaid};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Output {
+    /// Human readable tree
+    Tree,
+    /// JSON document keyed by region
+    Json,
+    /// Graphviz DOT graph
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+pub(crate) trait Render {
+    fn trees(&self) -> Vec<ptree::item::StringItem>;
+
+    fn json(&self) -> serde_json::Value;
+
+    fn dot(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    fn mermaid(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Problems that left something out without failing the region
+    fn warnings(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Report {
+    output: Output,
+    json: serde_json::Map<String, serde_json::Value>,
+    graphs: Vec<(String, Vec<String>)>,
+    warnings: Vec<(String, String)>,
+    regions: usize,
+    skipped: Vec<(String, anyhow::Error)>,
+}
+
+impl Report {
+    pub(crate) fn new(output: Output) -> Self {
+        Self {
+            output,
+            json: serde_json::Map::new(),
+            graphs: vec![],
+            warnings: vec![],
+            regions: 0,
+            skipped: vec![],
+        }
+    }
+
+    pub(crate) fn add(&mut self, region: String, resources: &impl Render) -> anyhow::Result<()> {
+        self.regions += 1;
+        self.warnings.extend(
+            resources
+                .warnings()
+                .into_iter()
+                .map(|warning| (region.clone(), warning)),
+        );
+        match self.output {
+            Output::Tree => {
+                // Trees of several regions follow each other, so say which one they belong to
+                println!();
+                println!("Region {region}");
+                resources.trees().iter().for_each(|tree| {
+                    println!();
+                    ptree::print_tree(tree).expect("Failed to print tree");
+                });
+            }
+            Output::Json => {
+                self.json.insert(region, resources.json());
+            }
+            Output::Dot => {
+                let statements = resources
+                    .dot()
+                    .ok_or_else(|| anyhow::anyhow!("DOT output is not supported here"))?;
+                self.graphs.push((region, statements));
+            }
+            Output::Mermaid => {
+                let statements = resources
+                    .mermaid()
+                    .ok_or_else(|| anyhow::anyhow!("Mermaid output is not supported here"))?;
+                self.graphs.push((region, statements));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remember a region that failed, so that it is reported once everything else is printed
+    pub(crate) fn skip(&mut self, region: String, error: anyhow::Error) {
+        self.skipped.push((region, error));
+    }
+
+    pub(crate) fn finish(self) -> anyhow::Result<()> {
+        match self.output {
+            Output::Tree => {}
+            Output::Json => println!("{}", serde_json::to_string_pretty(&self.json)?),
+            Output::Dot => print!("{}", dot::graph(self.graphs)),
+            Output::Mermaid => print!("{}", mermaid::flowchart(self.graphs)),
+        }
+
+        for (region, warning) in self.warnings {
+            eprintln!("Warning: region {region}: {warning}");
+        }
+        let skipped = self.skipped.len();
+        for (region, error) in self.skipped {
+            eprintln!("Warning: skipped region {region}: {error:#}");
+        }
+
+        // Skipping some regions is fine, but with nothing left the run failed
+        if self.regions == 0 && skipped > 0 {
+            anyhow::bail!("all {skipped} regions failed");
+        }
+
+        Ok(())
+    }
+}
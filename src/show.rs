@@ -1,5 +1,10 @@
 use aws_sdk_cloudformation as cf;
 use aws_sdk_ec2 as ec2;
+use aws_sdk_eks as eks;
+use aws_sdk_elasticloadbalancing as elb;
+use aws_sdk_elasticloadbalancingv2 as elbv2;
+use aws_sdk_lambda as lambda;
+use aws_sdk_rds as rds;
 use aws_types::region::Region;
 use duplicate::duplicate_item;
 
@@ -25,6 +30,13 @@ pub(crate) trait Show {
 
         format!("{}{name}", self.id())
     }
+
+    fn json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id(),
+            "name": self.name().or_else(|| self.description()),
+        })
+    }
 }
 
 impl Show for Option<&Region> {
@@ -54,6 +66,16 @@ impl Show for Option<&Region> {
     [NatGateway] [nat_gateway_id];
     [VpnConnection] [vpn_connection_id];
     [VpnGateway] [vpn_gateway_id];
+    [CustomerGateway] [customer_gateway_id];
+    [TransitGateway] [transit_gateway_id];
+    [TransitGatewayAttachment] [transit_gateway_attachment_id];
+    [TransitGatewayRouteTable] [transit_gateway_route_table_id];
+    [EgressOnlyInternetGateway] [egress_only_internet_gateway_id];
+    [CarrierGateway] [carrier_gateway_id];
+    [DhcpOptions] [dhcp_options_id];
+    [Volume] [volume_id];
+    [Snapshot] [snapshot_id];
+    [LaunchTemplate] [launch_template_id];
 )]
 impl Show for &ec2::types::resource {
     fn id(&self) -> String {
@@ -89,6 +111,22 @@ impl Show for &ec2::types::SecurityGroup {
     }
 }
 
+impl Show for &ec2::types::Address {
+    fn id(&self) -> String {
+        self.allocation_id()
+            .or_else(|| self.public_ip())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    fn tag(&self, key: &str) -> Option<&str> {
+        self.tags()?
+            .iter()
+            .find(|tag| tag.key.as_deref() == Some(key))?
+            .value()
+    }
+}
+
 impl Show for &ec2::types::NetworkInterface {
     fn id(&self) -> String {
         self.network_interface_id.clone().unwrap_or_default()
@@ -106,6 +144,109 @@ impl Show for &ec2::types::NetworkInterface {
     }
 }
 
+impl Show for &elbv2::types::LoadBalancer {
+    fn id(&self) -> String {
+        self.load_balancer_name().unwrap_or_default().to_string()
+    }
+
+    fn tag(&self, _key: &str) -> Option<&str> {
+        None
+    }
+}
+
+impl Show for &elbv2::types::TargetGroup {
+    fn id(&self) -> String {
+        self.target_group_name().unwrap_or_default().to_string()
+    }
+
+    fn tag(&self, _key: &str) -> Option<&str> {
+        None
+    }
+}
+
+impl Show for &elb::types::LoadBalancerDescription {
+    fn id(&self) -> String {
+        self.load_balancer_name().unwrap_or_default().to_string()
+    }
+
+    fn tag(&self, _key: &str) -> Option<&str> {
+        None
+    }
+}
+
+#[duplicate_item(
+    resource id_accessor;
+    [DbCluster] [db_cluster_identifier];
+    [DbInstance] [db_instance_identifier];
+    [DbSnapshot] [db_snapshot_identifier];
+    [DbClusterSnapshot] [db_cluster_snapshot_identifier];
+)]
+impl Show for &rds::types::resource {
+    fn id(&self) -> String {
+        self.id_accessor().unwrap_or_default().to_string()
+    }
+
+    fn tag(&self, key: &str) -> Option<&str> {
+        self.tag_list()?
+            .iter()
+            .find(|tag| tag.key() == Some(key))?
+            .value()
+    }
+}
+
+#[duplicate_item(
+    resource id_accessor description_field;
+    [DbSubnetGroup] [db_subnet_group_name] [db_subnet_group_description];
+    [DbParameterGroup] [db_parameter_group_name] [description];
+    [DbClusterParameterGroup] [db_cluster_parameter_group_name] [description];
+)]
+impl Show for &rds::types::resource {
+    fn id(&self) -> String {
+        self.id_accessor().unwrap_or_default().to_string()
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description_field.as_deref()
+    }
+
+    fn tag(&self, _key: &str) -> Option<&str> {
+        None
+    }
+}
+
+#[duplicate_item(
+    resource id_accessor;
+    [Cluster] [name];
+    [Nodegroup] [nodegroup_name];
+    [FargateProfile] [fargate_profile_name];
+    [Addon] [addon_name];
+)]
+impl Show for &eks::types::resource {
+    fn id(&self) -> String {
+        self.id_accessor().unwrap_or_default().to_string()
+    }
+
+    fn tag(&self, key: &str) -> Option<&str> {
+        self.tags()?.get(key).map(String::as_str)
+    }
+}
+
+impl Show for &lambda::types::FunctionConfiguration {
+    fn id(&self) -> String {
+        self.function_name.clone().unwrap_or_default()
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description
+            .as_deref()
+            .filter(|description| !description.is_empty())
+    }
+
+    fn tag(&self, _key: &str) -> Option<&str> {
+        None
+    }
+}
+
 impl Show for &cf::types::Stack {
     fn id(&self) -> String {
         self.stack_id().unwrap_or_default().to_string()